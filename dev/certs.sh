@@ -0,0 +1,32 @@
+#!/bin/bash
+# Makes a throwaway CA, a server certificate for localhost and a client certificate signed by
+# the CA, to try the secure context with `CLIENT_CA_BUNDLE` locally.
+#
+# SSL_CERT="dev/certs/server.pem" SSL_KEY="dev/certs/server-key.pem"
+# CLIENT_CA_BUNDLE="dev/certs/ca.pem"
+#
+# curl --cacert dev/certs/ca.pem --cert dev/certs/client.pem --key dev/certs/client-key.pem \
+#   https://localhost:8340/ily
+set -e
+
+directory="$(dirname "$0")/certs"
+mkdir -p "$directory"
+cd "$directory"
+
+openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes -days 30 \
+  -subj "/CN=Koii local CA" -keyout ca-key.pem -out ca.pem
+
+openssl req -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes \
+  -subj "/CN=localhost" -keyout server-key.pem -out server.csr
+openssl x509 -req -in server.csr -CA ca.pem -CAkey ca-key.pem -CAcreateserial -days 30 \
+  -extfile <(printf "subjectAltName=DNS:localhost,IP:127.0.0.1\nextendedKeyUsage=serverAuth") \
+  -out server.pem
+
+openssl req -newkey ec -pkeyopt ec_paramgen_curve:prime256v1 -nodes \
+  -subj "/CN=Koii local edge" -keyout client-key.pem -out client.csr
+openssl x509 -req -in client.csr -CA ca.pem -CAkey ca-key.pem -CAcreateserial -days 30 \
+  -extfile <(printf "extendedKeyUsage=clientAuth") \
+  -out client.pem
+
+rm -f server.csr client.csr ca.srl
+echo "Certificates are in $directory."
@@ -0,0 +1,69 @@
+use axum::{
+    Json,
+    http::{ HeaderName, StatusCode, header::{ CACHE_CONTROL, PRAGMA } },
+    response::AppendHeaders,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+// OAuth endpoints talk to standard client libraries, so they answer in the RFC 6749 shape
+// instead of `ResponseBody`.
+
+pub type OAuthResponseModel = (
+    StatusCode,
+    AppendHeaders<[(HeaderName, &'static str); 2]>,
+    Json<Value>,
+);
+
+#[derive(Serialize)]
+struct OAuthErrorBody<'a> {
+    error: &'a str,
+    error_description: &'a str,
+}
+
+pub fn result<R: Serialize>(result: R) -> OAuthResponseModel {
+    match serde_json::to_value(result) {
+        Ok(result) => (StatusCode::OK, no_store(), Json(result)),
+        Err(_) => server_error(),
+    }
+}
+
+/// Same as `result`, for endpoints creating something.
+pub fn created<R: Serialize>(result: R) -> OAuthResponseModel {
+    match serde_json::to_value(result) {
+        Ok(result) => (StatusCode::CREATED, no_store(), Json(result)),
+        Err(_) => server_error(),
+    }
+}
+
+pub fn error(status: StatusCode, error: &str, description: &str) -> OAuthResponseModel {
+    let body = OAuthErrorBody {
+        error,
+        error_description: description,
+    };
+
+    match serde_json::to_value(body) {
+        Ok(body) => (status, no_store(), Json(body)),
+        Err(_) => server_error(),
+    }
+}
+
+pub fn server_error() -> OAuthResponseModel {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        no_store(),
+        Json(
+            serde_json::json!({
+                "error": "server_error",
+                "error_description": "Something went wrong while processing your request."
+            })
+        ),
+    )
+}
+
+fn no_store() -> AppendHeaders<[(HeaderName, &'static str); 2]> {
+    AppendHeaders([
+        (CACHE_CONTROL, "no-store"),
+        (PRAGMA, "no-cache"),
+    ])
+}
@@ -22,7 +22,8 @@ pub struct AccountDocument {
     /// Account's email.
     pub email: String,
 
-    /// Account's password hash using argon2id.
+    /// Account's password hash using argon2id, empty for accounts made through an upstream
+    /// identity provider.
     pub password_hash: String,
 
     pub mfa_status: AccountMfaStatus,
@@ -49,6 +50,12 @@ pub struct AccountDocument {
     pub deletion_requested: Option<bson::DateTime>,
 }
 
+impl AccountDocument {
+    pub fn has_password(&self) -> bool {
+        !self.password_hash.is_empty()
+    }
+}
+
 pub struct AccountOperations {
     collection: Collection<AccountDocument>,
 }
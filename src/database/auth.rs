@@ -1,11 +1,15 @@
 use std::time::Duration;
 
 use mongodb::{ Collection, IndexModel, bson, error::WriteFailure, options::IndexOptions };
-use redis::{ AsyncCommands, RedisError, aio::MultiplexedConnection };
+use redis::{ AsyncCommands, RedisError };
 use serde::{ Deserialize, Serialize };
 use thiserror::Error;
 
-use crate::{ env::REFRESH_MAX_AGE, utils::{ jwt::KeyClaims, timestamp } };
+use crate::{
+    database::cache::CacheConnection,
+    env::REFRESH_MAX_AGE,
+    utils::{ jwt::KeyClaims, metrics, timestamp },
+};
 
 #[derive(Deserialize, Serialize)]
 pub struct AuthDocument {
@@ -29,12 +33,12 @@ pub enum AuthOperationError {
 #[derive(Clone)]
 pub struct AuthOperations {
     collection: Collection<AuthDocument>,
-    cache: MultiplexedConnection,
+    cache: CacheConnection,
 }
 impl AuthOperations {
     pub async fn new(
         collection: Collection<AuthDocument>,
-        cache: MultiplexedConnection
+        cache: CacheConnection
     ) -> Result<Self, AuthOperationError> {
         collection.create_index(
             IndexModel::builder()
@@ -110,6 +114,8 @@ impl AuthOperations {
             format!("account:{}:token:{}", claims.account_id, claims.identifier)
         ).await?;
 
+        metrics::auth_cache(status.is_some());
+
         return match status {
             Some(true) => Ok(current_time <= claims.exp),
             Some(false) => Ok(false),
@@ -0,0 +1,103 @@
+use mongodb::{ Collection, IndexModel, bson, error::WriteFailure, options::IndexOptions };
+use serde::{ Deserialize, Serialize };
+
+use crate::env::OAUTH_CODE_MAX_AGE;
+
+#[derive(Deserialize, Serialize)]
+pub struct AuthorizationCodeDocument {
+    /// The code handed to the client through the redirect.
+    pub code: String,
+
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    pub client_id: String,
+
+    /// Must be sent again, unchanged, when the code is redeemed.
+    pub redirect_uri: String,
+
+    pub scope: Vec<String>,
+
+    /// PKCE `S256` challenge, required for public clients.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_challenge: Option<String>,
+
+    /// OpenID Connect `nonce`, echoed back in the ID token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+
+    /// The last time the user proved who they are.
+    pub auth_time: bson::DateTime,
+
+    /// Authentication methods used for this authorization, see `utils::oidc`.
+    pub amr: Vec<String>,
+
+    /// TTL: OAUTH_CODE_MAX_AGE
+    pub issued_at: bson::DateTime,
+}
+
+pub struct AuthorizationCodeOperations {
+    collection: Collection<AuthorizationCodeDocument>,
+}
+
+impl AuthorizationCodeOperations {
+    pub async fn new(
+        collection: Collection<AuthorizationCodeDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "code": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "issued_at": 1 })
+                .options(IndexOptions::builder().expire_after(*OAUTH_CODE_MAX_AGE).build())
+                .build()
+        ).await?;
+
+        Ok(AuthorizationCodeOperations { collection })
+    }
+
+    pub async fn add(
+        &self,
+        document: &AuthorizationCodeDocument
+    ) -> Result<bool, mongodb::error::Error> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Codes are single use, the document is gone once this returns it.
+    ///
+    /// The TTL monitor only runs every minute, so the age is checked here as well.
+    pub async fn consume(
+        &self,
+        code: &str
+    ) -> Result<Option<AuthorizationCodeDocument>, mongodb::error::Error> {
+        let document = self.collection.find_one_and_delete(bson::doc! { "code": code }).await?;
+
+        Ok(
+            document.filter(|document| {
+                document.issued_at.to_system_time().elapsed().unwrap_or_default() <=
+                    *OAUTH_CODE_MAX_AGE
+            })
+        )
+    }
+}
@@ -0,0 +1,56 @@
+use redis::{
+    Arg,
+    Cmd,
+    Pipeline,
+    RedisFuture,
+    Value,
+    aio::{ ConnectionLike, MultiplexedConnection },
+};
+use tracing::Instrument;
+
+/// The shared Redis connection, every command runs in its own span so it shows up in traces.
+#[derive(Clone)]
+pub struct CacheConnection {
+    connection: MultiplexedConnection,
+}
+
+impl CacheConnection {
+    pub fn new(connection: MultiplexedConnection) -> Self {
+        CacheConnection { connection }
+    }
+}
+
+impl ConnectionLike for CacheConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let span = tracing::info_span!(
+            "redis",
+            otel.name = command_name(cmd),
+            db.system = "redis",
+        );
+
+        Box::pin(self.connection.req_packed_command(cmd).instrument(span))
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let span = tracing::info_span!("redis", otel.name = "PIPELINE", db.system = "redis");
+
+        Box::pin(self.connection.req_packed_commands(cmd, offset, count).instrument(span))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.connection.get_db()
+    }
+}
+
+/// Only the command itself, keys and values can hold account IDs.
+fn command_name(cmd: &Cmd) -> String {
+    match cmd.args_iter().next() {
+        Some(Arg::Simple(name)) => String::from_utf8_lossy(name).to_uppercase(),
+        _ => "UNKNOWN".to_string(),
+    }
+}
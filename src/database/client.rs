@@ -0,0 +1,438 @@
+use std::{ collections::BTreeMap, net::IpAddr };
+
+use mongodb::{ Collection, IndexModel, bson, error::WriteFailure, options::IndexOptions };
+use serde::{ Deserialize, Serialize };
+use url::{ Host, Url };
+
+use crate::utils::oauth::{ GRANT_TYPES, SENSITIVE_SCOPES, TOKEN_EXCHANGE_GRANT };
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ClientDocument {
+    /// Unique ID to the OAuth client.
+    pub client_id: String,
+
+    /// Name shown to the user when the client asks for access.
+    pub name: String,
+
+    /// Client secret hash using argon2id, only confidential clients have one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret_hash: Option<String>,
+
+    /// Exact redirect URIs the client is allowed to send the user back to.
+    pub redirect_uris: Vec<String>,
+
+    /// Every scope the client is allowed to request.
+    pub scopes: Vec<String>,
+
+    /// Grant types the client may use at `/oauth/token`.
+    #[serde(default = "default_grant_types")]
+    pub grant_types: Vec<String>,
+
+    /// Why the client needs each sensitive scope, shown to the user on the consent screen.
+    #[serde(default)]
+    pub purposes: BTreeMap<String, String>,
+
+    /// Pairwise subjects are derived from this value, clients run by the same operator
+    /// share it to see the same subject for an account.
+    pub sector_identifier: String,
+
+    /// Clients this client may exchange a user's token for, see RFC 8693.
+    #[serde(default)]
+    pub delegation_targets: Vec<String>,
+
+    /// Clients allowed to exchange a user's token for one bound to this client, a delegation
+    /// only goes through when both sides list each other.
+    #[serde(default)]
+    pub delegation_actors: Vec<String>,
+
+    /// Where logout tokens are sent when the user logs out of Koii.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backchannel_logout_uri: Option<String>,
+
+    /// Registration access token hash, only clients registered dynamically have one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registration_token_hash: Option<String>,
+
+    pub issued_at: bson::DateTime,
+}
+
+/// Why a client's metadata was refused, split the way RFC 7591 reports it.
+pub enum ClientMetadataError {
+    RedirectUri(&'static str),
+    Metadata(&'static str),
+}
+
+impl ClientDocument {
+    pub fn is_confidential(&self) -> bool {
+        self.secret_hash.is_some()
+    }
+
+    /// The declared reason for a scope, sensitive scopes can't be requested without one.
+    pub fn purpose(&self, scope: &str) -> Option<&str> {
+        self.purposes
+            .get(scope)
+            .map(|purpose| purpose.trim())
+            .filter(|purpose| !purpose.is_empty())
+    }
+
+    /// Rules every client is held to, whether it was registered by hand or dynamically.
+    pub fn validate(&self) -> Result<(), ClientMetadataError> {
+        let name = self.name.trim();
+        if name.is_empty() || name.len() > 64 {
+            return Err(ClientMetadataError::Metadata("The name must be 1 to 64 characters."));
+        }
+
+        if self.sector_identifier.is_empty() {
+            return Err(ClientMetadataError::Metadata("A sector identifier is required."));
+        }
+
+        if
+            self.grant_types.is_empty() ||
+            !self.grant_types.iter().all(|grant_type| GRANT_TYPES.contains(&grant_type.as_str()))
+        {
+            return Err(ClientMetadataError::Metadata("Unsupported grant type."));
+        }
+
+        let uses = |grant_type: &str| {
+            self.grant_types.iter().any(|existing| existing == grant_type)
+        };
+
+        if !self.is_confidential() && (uses("client_credentials") || uses(TOKEN_EXCHANGE_GRANT)) {
+            return Err(
+                ClientMetadataError::Metadata("This grant type is only for confidential clients.")
+            );
+        }
+
+        if uses("authorization_code") && self.redirect_uris.is_empty() {
+            return Err(ClientMetadataError::RedirectUri("At least one redirect URI is required."));
+        }
+
+        for redirect_uri in &self.redirect_uris {
+            if !is_valid_redirect_uri(redirect_uri) {
+                return Err(
+                    ClientMetadataError::RedirectUri(
+                        "Redirect URIs must be absolute HTTPS URLs without a fragment."
+                    )
+                );
+            }
+        }
+
+        if self.backchannel_logout_uri.as_ref().is_some_and(|uri| !is_public_https_uri(uri)) {
+            return Err(
+                ClientMetadataError::Metadata(
+                    "The back-channel logout URI must be a public HTTPS URL."
+                )
+            );
+        }
+
+        if self.scopes.is_empty() {
+            return Err(ClientMetadataError::Metadata("At least one scope is required."));
+        }
+
+        for scope in &self.scopes {
+            if SENSITIVE_SCOPES.contains(&scope.as_str()) && self.purpose(scope).is_none() {
+                return Err(
+                    ClientMetadataError::Metadata("Every sensitive scope needs a declared purpose.")
+                );
+            }
+        }
+
+        if self.purposes.keys().any(|scope| !self.scopes.contains(scope)) {
+            return Err(ClientMetadataError::Metadata("A purpose was given for an unknown scope."));
+        }
+
+        if !self.delegation_targets.is_empty() && !uses(TOKEN_EXCHANGE_GRANT) {
+            return Err(
+                ClientMetadataError::Metadata(
+                    "Delegation targets require the token exchange grant."
+                )
+            );
+        }
+
+        if
+            self.delegation_targets.contains(&self.client_id) ||
+            self.delegation_actors.contains(&self.client_id)
+        {
+            return Err(ClientMetadataError::Metadata("A client can't delegate to itself."));
+        }
+
+        Ok(())
+    }
+}
+
+/// Plain HTTP is only allowed on loopback, for native apps and local development.
+fn is_valid_redirect_uri(redirect_uri: &str) -> bool {
+    let Ok(url) = Url::parse(redirect_uri) else {
+        return false;
+    };
+
+    if url.fragment().is_some() {
+        return false;
+    }
+
+    match url.scheme() {
+        "https" => url.host().is_some(),
+        "http" => matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]")),
+        _ => false,
+    }
+}
+
+/// Koii itself calls back-channel URIs, so they can't point at anything on its own network.
+///
+/// Only literal addresses are checked, names are trusted to resolve to where their owner says.
+fn is_public_https_uri(uri: &str) -> bool {
+    let Ok(url) = Url::parse(uri) else {
+        return false;
+    };
+
+    if url.scheme() != "https" || url.fragment().is_some() {
+        return false;
+    }
+
+    match url.host() {
+        Some(Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain != "localhost" && !domain.ends_with(".localhost")
+        }
+        Some(Host::Ipv4(address)) => is_public_ip(IpAddr::V4(address)),
+        Some(Host::Ipv6(address)) => is_public_ip(IpAddr::V6(address)),
+        None => false,
+    }
+}
+
+fn is_public_ip(address: IpAddr) -> bool {
+    match address.to_canonical() {
+        IpAddr::V4(address) => {
+            !(
+                address.is_loopback() ||
+                address.is_private() ||
+                address.is_link_local() ||
+                address.is_unspecified() ||
+                address.is_broadcast() ||
+                address.is_documentation() ||
+                // Carrier-grade NAT, 100.64.0.0/10.
+                (address.octets()[0] == 100 && (address.octets()[1] & 0xc0) == 64)
+            )
+        }
+        IpAddr::V6(address) => {
+            !(
+                address.is_loopback() ||
+                address.is_unspecified() ||
+                address.is_unique_local() ||
+                address.is_unicast_link_local()
+            )
+        }
+    }
+}
+
+fn default_grant_types() -> Vec<String> {
+    vec!["authorization_code".to_string(), "refresh_token".to_string()]
+}
+
+pub struct ClientOperations {
+    collection: Collection<ClientDocument>,
+}
+
+impl ClientOperations {
+    pub async fn new(
+        collection: Collection<ClientDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "client_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        Ok(ClientOperations { collection })
+    }
+
+    pub async fn add(&self, document: &ClientDocument) -> Result<bool, mongodb::error::Error> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub async fn get(
+        &self,
+        client_id: &str
+    ) -> Result<Option<ClientDocument>, mongodb::error::Error> {
+        self.collection.find_one(bson::doc! { "client_id": client_id }).await
+    }
+
+    /// Replaces the whole client, keyed by its ID.
+    pub async fn update(&self, document: &ClientDocument) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.replace_one(
+            bson::doc! { "client_id": &document.client_id },
+            document
+        ).await?;
+
+        Ok(result.matched_count == 1)
+    }
+
+    pub async fn remove(&self, client_id: &str) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.delete_one(bson::doc! { "client_id": client_id }).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> ClientDocument {
+        ClientDocument {
+            client_id: "client".to_string(),
+            name: "Client".to_string(),
+            secret_hash: Some("hash".to_string()),
+            redirect_uris: vec!["https://app.example/callback".to_string()],
+            scopes: vec!["openid".to_string()],
+            grant_types: default_grant_types(),
+            purposes: BTreeMap::new(),
+            sector_identifier: "app.example".to_string(),
+            delegation_targets: Vec::new(),
+            delegation_actors: Vec::new(),
+            backchannel_logout_uri: None,
+            registration_token_hash: None,
+            issued_at: bson::DateTime::now(),
+        }
+    }
+
+    fn metadata_error(client: &ClientDocument) -> Option<&'static str> {
+        match client.validate() {
+            Ok(()) => None,
+            Err(ClientMetadataError::Metadata(description)) => Some(description),
+            Err(ClientMetadataError::RedirectUri(description)) => Some(description),
+        }
+    }
+
+    #[test]
+    fn accepts_a_plain_client() {
+        assert!(client().validate().is_ok());
+    }
+
+    #[test]
+    fn redirect_uris_need_https_outside_loopback() {
+        for redirect_uri in ["http://127.0.0.1:8080/cb", "http://localhost/cb", "http://[::1]/cb"] {
+            let mut client = client();
+            client.redirect_uris = vec![redirect_uri.to_string()];
+            assert!(client.validate().is_ok(), "{redirect_uri}");
+        }
+
+        let refused = ["http://app.example/cb", "https://app.example/cb#x", "app://cb", "/cb"];
+        for redirect_uri in refused {
+            let mut client = client();
+            client.redirect_uris = vec![redirect_uri.to_string()];
+            assert!(
+                matches!(client.validate(), Err(ClientMetadataError::RedirectUri(_))),
+                "{redirect_uri}"
+            );
+        }
+    }
+
+    #[test]
+    fn backchannel_logout_uri_must_be_public_https() {
+        for uri in ["https://app.example/logout", "https://203.0.113.0.example/logout"] {
+            let mut client = client();
+            client.backchannel_logout_uri = Some(uri.to_string());
+            assert!(client.validate().is_ok(), "{uri}");
+        }
+
+        let refused = [
+            "http://app.example/logout",
+            "https://localhost/logout",
+            "https://api.localhost./logout",
+            "https://127.0.0.1/logout",
+            "https://10.0.0.8/logout",
+            "https://192.168.1.1/logout",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/logout",
+            "https://0.0.0.0/logout",
+            "https://[::1]/logout",
+            "https://[::ffff:127.0.0.1]/logout",
+            "https://[fd00::1]/logout",
+            "https://[fe80::1]/logout",
+        ];
+        for uri in refused {
+            let mut client = client();
+            client.backchannel_logout_uri = Some(uri.to_string());
+            assert!(metadata_error(&client).is_some(), "{uri}");
+        }
+    }
+
+    #[test]
+    fn authorization_code_needs_a_redirect_uri() {
+        let mut client = client();
+        client.redirect_uris.clear();
+        assert!(matches!(client.validate(), Err(ClientMetadataError::RedirectUri(_))));
+
+        client.grant_types = vec!["client_credentials".to_string()];
+        assert!(client.validate().is_ok());
+    }
+
+    #[test]
+    fn public_clients_cant_use_confidential_grants() {
+        let mut client = client();
+        client.secret_hash = None;
+        client.grant_types = vec!["client_credentials".to_string()];
+        assert!(metadata_error(&client).is_some());
+
+        client.grant_types = vec![TOKEN_EXCHANGE_GRANT.to_string()];
+        assert!(metadata_error(&client).is_some());
+    }
+
+    #[test]
+    fn rejects_unknown_grant_types() {
+        let mut client = client();
+        client.grant_types = vec!["password".to_string()];
+        assert_eq!(metadata_error(&client), Some("Unsupported grant type."));
+    }
+
+    #[test]
+    fn sensitive_scopes_need_a_purpose() {
+        let mut client = client();
+        client.scopes.push("email".to_string());
+        assert!(metadata_error(&client).is_some());
+
+        client.purposes.insert("email".to_string(), "  ".to_string());
+        assert!(metadata_error(&client).is_some());
+
+        client.purposes.insert("email".to_string(), "Receipts.".to_string());
+        assert!(client.validate().is_ok());
+
+        client.purposes.insert("profile".to_string(), "Avatar.".to_string());
+        assert!(metadata_error(&client).is_some());
+    }
+
+    #[test]
+    fn delegation_needs_token_exchange_and_another_client() {
+        let mut client = client();
+        client.delegation_targets = vec!["other".to_string()];
+        assert!(metadata_error(&client).is_some());
+
+        client.grant_types.push(TOKEN_EXCHANGE_GRANT.to_string());
+        assert!(client.validate().is_ok());
+
+        client.delegation_targets = vec!["client".to_string()];
+        assert!(metadata_error(&client).is_some());
+
+        client.delegation_targets.clear();
+        client.delegation_actors = vec!["client".to_string()];
+        assert!(metadata_error(&client).is_some());
+    }
+}
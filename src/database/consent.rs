@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+
+use mongodb::{ Collection, IndexModel, bson, options::IndexOptions };
+use serde::{ Deserialize, Serialize };
+
+/// Scopes an account granted to a client, with the reasons it was shown at the time.
+#[derive(Deserialize, Serialize)]
+pub struct ConsentDocument {
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    pub client_id: String,
+
+    /// Every scope granted to the client so far.
+    pub scope: Vec<String>,
+
+    /// Justification the client declared for each sensitive scope when the user agreed to it.
+    #[serde(default)]
+    pub purposes: BTreeMap<String, String>,
+
+    /// The first time the user agreed to anything for this client.
+    pub granted_at: bson::DateTime,
+
+    /// The last time the user agreed to a scope for this client.
+    pub updated_at: bson::DateTime,
+}
+
+pub struct ConsentOperations {
+    collection: Collection<ConsentDocument>,
+}
+
+impl ConsentOperations {
+    pub async fn new(
+        collection: Collection<ConsentDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "account_id": 1, "client_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        Ok(ConsentOperations { collection })
+    }
+
+    /// Adds scopes to the client's consent, creating it on the first grant.
+    pub async fn grant(
+        &self,
+        account_id: &str,
+        client_id: &str,
+        scope: &[String],
+        purposes: &BTreeMap<String, String>
+    ) -> Result<(), mongodb::error::Error> {
+        let now = bson::DateTime::now();
+
+        let mut set = bson::doc! { "updated_at": now };
+        for (scope, purpose) in purposes {
+            set.insert(format!("purposes.{}", scope), purpose.as_str());
+        }
+
+        self.collection
+            .update_one(
+                bson::doc! { "account_id": account_id, "client_id": client_id },
+                bson::doc! {
+                    "$addToSet": { "scope": { "$each": scope.to_vec() } },
+                    "$set": set,
+                    "$setOnInsert": { "granted_at": now }
+                }
+            )
+            .upsert(true).await?;
+
+        Ok(())
+    }
+
+    pub async fn get(
+        &self,
+        account_id: &str,
+        client_id: &str
+    ) -> Result<Option<ConsentDocument>, mongodb::error::Error> {
+        self.collection.find_one(
+            bson::doc! { "account_id": account_id, "client_id": client_id }
+        ).await
+    }
+
+    pub async fn list(
+        &self,
+        account_id: &str
+    ) -> Result<Vec<ConsentDocument>, mongodb::error::Error> {
+        let mut cursor = self.collection.find(bson::doc! { "account_id": account_id }).await?;
+
+        let mut documents = Vec::new();
+        while cursor.advance().await? {
+            documents.push(cursor.deserialize_current()?);
+        }
+
+        Ok(documents)
+    }
+
+    pub async fn revoke(
+        &self,
+        account_id: &str,
+        client_id: &str
+    ) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.delete_one(
+            bson::doc! { "account_id": account_id, "client_id": client_id }
+        ).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+
+    /// Forgets every consent given to a client, for when it's deleted.
+    pub async fn revoke_client(&self, client_id: &str) -> Result<(), mongodb::error::Error> {
+        self.collection.delete_many(bson::doc! { "client_id": client_id }).await?;
+        Ok(())
+    }
+}
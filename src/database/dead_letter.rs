@@ -0,0 +1,45 @@
+use mongodb::{ Collection, IndexModel, bson };
+use serde::{ Deserialize, Serialize };
+
+/// A job a worker gave up on after running out of attempts, kept for someone to look at.
+#[derive(Deserialize, Serialize)]
+pub struct DeadLetterDocument {
+    /// The worker that gave up, e.g. `backchannel_logout`.
+    pub worker: String,
+
+    /// Where the job was headed.
+    pub target: String,
+
+    /// Enough about the job to redo it by hand.
+    pub payload: bson::Document,
+
+    pub attempts: u32,
+
+    /// What went wrong on the last attempt.
+    pub last_error: String,
+
+    pub failed_at: bson::DateTime,
+}
+
+#[derive(Clone)]
+pub struct DeadLetterOperations {
+    collection: Collection<DeadLetterDocument>,
+}
+
+impl DeadLetterOperations {
+    pub async fn new(
+        collection: Collection<DeadLetterDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder().keys(bson::doc! { "worker": 1, "failed_at": -1 }).build()
+        ).await?;
+
+        Ok(DeadLetterOperations { collection })
+    }
+
+    pub async fn add(&self, document: &DeadLetterDocument) -> Result<(), mongodb::error::Error> {
+        self.collection.insert_one(document).await?;
+
+        Ok(())
+    }
+}
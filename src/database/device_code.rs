@@ -0,0 +1,190 @@
+use mongodb::{
+    Collection,
+    IndexModel,
+    bson,
+    error::WriteFailure,
+    options::{ IndexOptions, ReturnDocument },
+};
+use serde::{ Deserialize, Serialize };
+
+use crate::env::DEVICE_CODE_MAX_AGE;
+
+#[derive(Clone, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceCodeStatus {
+    Pending,
+    Approved,
+    Denied,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DeviceCodeDocument {
+    /// The code the device polls `/oauth/token` with.
+    pub device_code: String,
+
+    /// The short code the user types on the verification page, stored without formatting.
+    pub user_code: String,
+
+    pub client_id: String,
+
+    pub scope: Vec<String>,
+
+    pub status: DeviceCodeStatus,
+
+    /// Set once a user approved or denied the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_time: Option<bson::DateTime>,
+    #[serde(default)]
+    pub amr: Vec<String>,
+
+    /// Minimum seconds between two polls, raised every time the device polls too fast.
+    pub interval: u64,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_polled_at: Option<bson::DateTime>,
+
+    /// TTL: DEVICE_CODE_MAX_AGE
+    pub issued_at: bson::DateTime,
+}
+
+impl DeviceCodeDocument {
+    /// The TTL monitor only runs every minute, so the age is checked here as well.
+    pub fn is_expired(&self) -> bool {
+        self.issued_at.to_system_time().elapsed().unwrap_or_default() > *DEVICE_CODE_MAX_AGE
+    }
+}
+
+pub struct DeviceCodeOperations {
+    collection: Collection<DeviceCodeDocument>,
+}
+
+impl DeviceCodeOperations {
+    pub async fn new(
+        collection: Collection<DeviceCodeDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "device_code": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "user_code": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "issued_at": 1 })
+                .options(IndexOptions::builder().expire_after(*DEVICE_CODE_MAX_AGE).build())
+                .build()
+        ).await?;
+
+        Ok(DeviceCodeOperations { collection })
+    }
+
+    pub async fn add(&self, document: &DeviceCodeDocument) -> Result<bool, mongodb::error::Error> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub async fn get_from_user_code(
+        &self,
+        user_code: &str
+    ) -> Result<Option<DeviceCodeDocument>, mongodb::error::Error> {
+        let document = self.collection.find_one(bson::doc! { "user_code": user_code }).await?;
+
+        Ok(document.filter(|document| !document.is_expired()))
+    }
+
+    /// Settles a pending request, a request can only be approved or denied once.
+    pub async fn settle(
+        &self,
+        user_code: &str,
+        approved: bool,
+        account_id: &str,
+        auth_time: bson::DateTime,
+        amr: &[String]
+    ) -> Result<bool, mongodb::error::Error> {
+        let status = match approved {
+            true => "approved",
+            false => "denied",
+        };
+
+        let result = self.collection.update_one(
+            bson::doc! { "user_code": user_code, "status": "pending" },
+            bson::doc! {
+                "$set": {
+                    "status": status,
+                    "account_id": account_id,
+                    "auth_time": auth_time,
+                    "amr": amr.to_vec()
+                }
+            }
+        ).await?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    /// Turns an approval into a denial when what goes with it couldn't be recorded.
+    pub async fn withdraw(&self, user_code: &str) -> Result<(), mongodb::error::Error> {
+        self.collection.update_one(
+            bson::doc! { "user_code": user_code, "status": "approved" },
+            bson::doc! { "$set": { "status": "denied" } }
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Marks a poll from the device and returns the request as it was before this poll.
+    pub async fn poll(
+        &self,
+        device_code: &str,
+        client_id: &str
+    ) -> Result<Option<DeviceCodeDocument>, mongodb::error::Error> {
+        self.collection
+            .find_one_and_update(
+                bson::doc! { "device_code": device_code, "client_id": client_id },
+                bson::doc! { "$set": { "last_polled_at": bson::DateTime::now() } }
+            )
+            .return_document(ReturnDocument::Before).await
+    }
+
+    pub async fn slow_down(&self, device_code: &str) -> Result<(), mongodb::error::Error> {
+        self.collection.update_one(
+            bson::doc! { "device_code": device_code },
+            bson::doc! { "$inc": { "interval": 5 } }
+        ).await?;
+
+        Ok(())
+    }
+
+    /// Device codes are single use, only one poll gets to redeem a settled request.
+    pub async fn consume(&self, device_code: &str) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.delete_one(
+            bson::doc! { "device_code": device_code, "status": { "$ne": "pending" } }
+        ).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+}
@@ -0,0 +1,160 @@
+use mongodb::{
+    Collection,
+    IndexModel,
+    bson,
+    options::{ IndexOptions, ReturnDocument },
+};
+use serde::{ Deserialize, Serialize };
+
+use crate::env::EMAIL_CODE_MAX_AGE;
+
+/// Codes are 6 digits, easy to type from a phone.
+pub const CODE_ALPHABET: [char; 10] = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+/// Wrong tries a code survives, a 6 digit code can't be guessed in so few.
+const MAX_ATTEMPTS: u32 = 5;
+
+#[derive(Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailCodePurpose {
+    /// Logging in without a password.
+    Login,
+
+    /// Elevating a session to sudo.
+    Sudo,
+}
+
+impl EmailCodePurpose {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EmailCodePurpose::Login => "login",
+            EmailCodePurpose::Sudo => "sudo",
+        }
+    }
+}
+
+/// A one-time code sent to the account's email, an account has at most one per purpose.
+#[derive(Deserialize, Serialize)]
+pub struct EmailCodeDocument {
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    pub purpose: EmailCodePurpose,
+
+    /// SHA-256 of the short code the user types in.
+    pub code_hash: String,
+
+    /// SHA-256 of the token in the emailed link, for purposes that send one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_hash: Option<String>,
+
+    /// Wrong codes entered so far, the code is thrown away past `MAX_ATTEMPTS`.
+    pub attempts: u32,
+
+    /// TTL: EMAIL_CODE_MAX_AGE
+    pub issued_at: bson::DateTime,
+}
+
+pub struct EmailCodeOperations {
+    collection: Collection<EmailCodeDocument>,
+}
+
+impl EmailCodeOperations {
+    pub async fn new(
+        collection: Collection<EmailCodeDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "account_id": 1, "purpose": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "link_hash": 1 })
+                .options(IndexOptions::builder().unique(true).sparse(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "issued_at": 1 })
+                .options(IndexOptions::builder().expire_after(*EMAIL_CODE_MAX_AGE).build())
+                .build()
+        ).await?;
+
+        Ok(EmailCodeOperations { collection })
+    }
+
+    /// Replaces whatever code the account had for the same purpose, only the latest email works.
+    pub async fn issue(&self, document: &EmailCodeDocument) -> Result<(), mongodb::error::Error> {
+        self.collection
+            .replace_one(
+                bson::doc! {
+                    "account_id": &document.account_id,
+                    "purpose": document.purpose.as_str()
+                },
+                document
+            )
+            .upsert(true).await?;
+
+        Ok(())
+    }
+
+    /// Checks a typed in code, every call counts as an attempt and a right code can only be
+    /// used once.
+    pub async fn attempt(
+        &self,
+        account_id: &str,
+        purpose: EmailCodePurpose,
+        code_hash: &str
+    ) -> Result<bool, mongodb::error::Error> {
+        let filter = bson::doc! { "account_id": account_id, "purpose": purpose.as_str() };
+
+        let document = self.collection
+            .find_one_and_update(filter.clone(), bson::doc! { "$inc": { "attempts": 1 } })
+            .return_document(ReturnDocument::After).await?;
+
+        let Some(document) = document else {
+            return Ok(false);
+        };
+
+        // The TTL monitor only runs every minute.
+        let expired =
+            document.issued_at.to_system_time().elapsed().unwrap_or_default() >
+            *EMAIL_CODE_MAX_AGE;
+        if expired || document.attempts > MAX_ATTEMPTS {
+            self.collection.delete_one(filter).await?;
+            return Ok(false);
+        }
+
+        if document.code_hash != code_hash {
+            return Ok(false);
+        }
+
+        let mut consume = filter;
+        consume.insert("code_hash", code_hash);
+        let result = self.collection.delete_one(consume).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+
+    /// Uses up the code behind an emailed link.
+    pub async fn redeem_link(
+        &self,
+        link_hash: &str,
+        purpose: EmailCodePurpose
+    ) -> Result<Option<EmailCodeDocument>, mongodb::error::Error> {
+        let document = self.collection.find_one_and_delete(
+            bson::doc! { "link_hash": link_hash, "purpose": purpose.as_str() }
+        ).await?;
+
+        Ok(
+            document.filter(|document| {
+                document.issued_at.to_system_time().elapsed().unwrap_or_default() <=
+                    *EMAIL_CODE_MAX_AGE
+            })
+        )
+    }
+}
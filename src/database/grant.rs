@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use mongodb::{ Collection, IndexModel, bson, error::WriteFailure, options::IndexOptions };
+use redis::{ AsyncCommands, RedisError };
+use serde::{ Deserialize, Serialize };
+use thiserror::Error;
+
+use crate::{
+    database::cache::CacheConnection,
+    env::REFRESH_MAX_AGE,
+    utils::{ jwt::ClientClaims, timestamp },
+};
+
+/// A set of tokens issued to an OAuth client, the access and refresh token share the identifier.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GrantDocument {
+    /// The token's identifier.
+    pub identifier: String,
+
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    /// The client the tokens were issued to.
+    pub client_id: String,
+
+    /// Scopes granted to the client.
+    pub scope: Vec<String>,
+
+    /// Carried over from the authorization, ID tokens from refreshes report the same values.
+    pub auth_time: bson::DateTime,
+    pub amr: Vec<String>,
+
+    /// TTL: REFRESH_MAX_AGE
+    pub issued_at: bson::DateTime,
+}
+
+#[derive(Error, Debug)]
+pub enum GrantOperationError {
+    #[error("Bad database")] Database(#[from] mongodb::error::Error),
+    #[error("Bad cache")] Cache(#[from] RedisError),
+}
+
+#[derive(Clone)]
+pub struct GrantOperations {
+    collection: Collection<GrantDocument>,
+    cache: CacheConnection,
+}
+impl GrantOperations {
+    pub async fn new(
+        collection: Collection<GrantDocument>,
+        cache: CacheConnection
+    ) -> Result<Self, GrantOperationError> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "identifier": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder().keys(bson::doc! { "account_id": 1, "client_id": 1 }).build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "issued_at": 1 })
+                .options(IndexOptions::builder().expire_after(*REFRESH_MAX_AGE).build())
+                .build()
+        ).await?;
+
+        Ok(GrantOperations { collection, cache })
+    }
+
+    /// Add grant to cache and database.
+    pub async fn issue(&mut self, document: &GrantDocument) -> Result<bool, GrantOperationError> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(GrantOperationError::Database(error));
+                    }
+                }
+            }
+        }
+
+        // Preload cache.
+        redis
+            ::cmd("SET")
+            .arg(format!("grant:{}", &document.identifier))
+            .arg(true)
+            .arg("EX")
+            .arg(REFRESH_MAX_AGE.as_secs())
+            .exec_async(&mut self.cache).await?;
+
+        Ok(true)
+    }
+
+    /// Same contract as `AuthOperations::check_token`, for tokens issued to clients.
+    pub async fn check(&mut self, claims: &ClientClaims) -> Result<bool, GrantOperationError> {
+        let current_time = timestamp::now();
+
+        let status = self.cache.get::<String, Option<bool>>(
+            format!("grant:{}", claims.identifier)
+        ).await?;
+
+        match status {
+            Some(true) => Ok(current_time <= claims.exp),
+            Some(false) => Ok(false),
+            None => self.refetch(claims, current_time).await,
+        }
+    }
+
+    pub async fn get(
+        &self,
+        identifier: &str
+    ) -> Result<Option<GrantDocument>, GrantOperationError> {
+        Ok(self.collection.find_one(bson::doc! { "identifier": identifier }).await?)
+    }
+
+    pub async fn revoke(&mut self, identifier: &str) -> Result<bool, GrantOperationError> {
+        self.cache.set::<String, bool, String>(format!("grant:{}", identifier), false).await?;
+
+        let db_result = self.collection.delete_one(
+            bson::doc! { "identifier": identifier }
+        ).await?;
+
+        Ok(db_result.deleted_count == 1)
+    }
+
+    /// Revokes every grant an account gave to a single client.
+    pub async fn revoke_client(
+        &mut self,
+        account_id: &str,
+        client_id: &str
+    ) -> Result<u64, GrantOperationError> {
+        self.revoke_many(bson::doc! { "account_id": account_id, "client_id": client_id }).await
+    }
+
+    /// Revokes every grant issued to a client, used when the client itself goes away.
+    pub async fn revoke_issued_to(&mut self, client_id: &str) -> Result<u64, GrantOperationError> {
+        self.revoke_many(bson::doc! { "client_id": client_id }).await
+    }
+
+    /// Revokes every grant an account gave to any client.
+    pub async fn revoke_all(&mut self, account_id: &str) -> Result<u64, GrantOperationError> {
+        self.revoke_many(bson::doc! { "account_id": account_id }).await
+    }
+
+    async fn revoke_many(&mut self, filter: bson::Document) -> Result<u64, GrantOperationError> {
+        let mut grants_cursor = self.collection.find(filter.clone()).await?;
+
+        // Loop through database to batch a cache request for all grants.
+        let mut mset_props: Vec<(String, bool)> = Vec::new();
+        while grants_cursor.advance().await? {
+            let grant_doc = grants_cursor.deserialize_current()?;
+            mset_props.push((format!("grant:{}", &grant_doc.identifier), false));
+        }
+
+        if mset_props.is_empty() {
+            return Ok(0);
+        }
+
+        self.cache.mset::<_, _, String>(&mset_props).await?;
+        let db_result = self.collection.delete_many(filter).await?;
+
+        Ok(db_result.deleted_count)
+    }
+
+    /// Cache miss, ask the database instead if this grant is valid or not.
+    async fn refetch(
+        &mut self,
+        claims: &ClientClaims,
+        current_time: Duration
+    ) -> Result<bool, GrantOperationError> {
+        let document = self.collection.find_one(
+            bson::doc! { "identifier": &claims.identifier, "client_id": &claims.client_id }
+        ).await?;
+
+        redis
+            ::cmd("SET")
+            .arg(format!("grant:{}", claims.identifier))
+            .arg(document.is_some() && current_time <= claims.exp)
+            .arg("EX")
+            .arg(REFRESH_MAX_AGE.as_secs())
+            .exec_async(&mut self.cache).await?;
+
+        Ok(document.is_some() && current_time <= claims.exp)
+    }
+}
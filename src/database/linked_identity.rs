@@ -0,0 +1,184 @@
+use mongodb::{
+    ClientSession,
+    Collection,
+    IndexModel,
+    bson,
+    error::WriteFailure,
+    options::IndexOptions,
+};
+use serde::{ Deserialize, Serialize };
+
+/// An account at an upstream identity provider that can sign in to a Koii account.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct LinkedIdentityDocument {
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    /// Name of the provider as configured in `UPSTREAM_PROVIDERS`.
+    pub provider: String,
+
+    /// Stable ID of the user at the provider, never their email.
+    pub subject: String,
+
+    /// Email the provider reported at link time, only shown for the user to recognize it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+
+    pub linked_at: bson::DateTime,
+}
+
+pub enum UnlinkOutcome {
+    Unlinked,
+    NotLinked,
+    /// Refused, nothing else would be left to sign in with.
+    LastIdentity,
+}
+
+pub struct LinkedIdentityOperations {
+    collection: Collection<LinkedIdentityDocument>,
+    mongo_client: mongodb::Client,
+}
+
+impl LinkedIdentityOperations {
+    pub async fn new(
+        collection: Collection<LinkedIdentityDocument>,
+        mongo_client: mongodb::Client
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "provider": 1, "subject": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder().keys(bson::doc! { "account_id": 1 }).build()
+        ).await?;
+
+        Ok(LinkedIdentityOperations { collection, mongo_client })
+    }
+
+    /// `false` when the identity already belongs to an account.
+    pub async fn link(
+        &self,
+        document: &LinkedIdentityDocument
+    ) -> Result<bool, mongodb::error::Error> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub async fn get(
+        &self,
+        provider: &str,
+        subject: &str
+    ) -> Result<Option<LinkedIdentityDocument>, mongodb::error::Error> {
+        self.collection.find_one(bson::doc! { "provider": provider, "subject": subject }).await
+    }
+
+    pub async fn get_for_account(
+        &self,
+        account_id: &str,
+        provider: &str
+    ) -> Result<Option<LinkedIdentityDocument>, mongodb::error::Error> {
+        self.collection.find_one(
+            bson::doc! { "account_id": account_id, "provider": provider }
+        ).await
+    }
+
+    pub async fn list(
+        &self,
+        account_id: &str
+    ) -> Result<Vec<LinkedIdentityDocument>, mongodb::error::Error> {
+        let mut cursor = self.collection.find(bson::doc! { "account_id": account_id }).await?;
+
+        let mut documents = Vec::new();
+        while cursor.advance().await? {
+            documents.push(cursor.deserialize_current()?);
+        }
+
+        Ok(documents)
+    }
+
+    pub async fn remove(
+        &self,
+        account_id: &str,
+        provider: &str,
+        subject: &str
+    ) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.delete_one(
+            bson::doc! { "account_id": account_id, "provider": provider, "subject": subject }
+        ).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+
+    /// Unlinks the account's identity at `provider`, `keep_last` refuses to unlink the only one.
+    ///
+    /// Runs in a transaction that also writes to the account, so two unlinks of the same account
+    /// conflict and the one retried sees what the other left instead of both counting two.
+    pub async fn unlink(
+        &self,
+        account_id: &str,
+        provider: &str,
+        keep_last: bool
+    ) -> Result<UnlinkOutcome, mongodb::error::Error> {
+        let mut session = self.mongo_client.start_session().await?;
+        let account_id = account_id.to_string();
+        let provider = provider.to_string();
+
+        session.start_transaction().and_run2(async move |session: &mut ClientSession| {
+            let database = session.client().database("koii");
+            let collection = database.collection::<LinkedIdentityDocument>("linked_identity");
+            let account_collection = database.collection::<bson::Document>("account");
+
+            account_collection
+                .update_one(
+                    bson::doc! { "account_id": &account_id },
+                    bson::doc! { "$currentDate": { "identities_changed_at": true } }
+                )
+                .session(&mut *session).await?;
+
+            let remaining = collection
+                .count_documents(bson::doc! { "account_id": &account_id })
+                .session(&mut *session).await?;
+
+            let linked = collection
+                .find_one(bson::doc! { "account_id": &account_id, "provider": &provider })
+                .session(&mut *session).await?;
+            if linked.is_none() {
+                return Ok(UnlinkOutcome::NotLinked);
+            }
+
+            if keep_last && remaining <= 1 {
+                return Ok(UnlinkOutcome::LastIdentity);
+            }
+
+            collection
+                .delete_one(bson::doc! { "account_id": &account_id, "provider": &provider })
+                .session(session).await?;
+
+            Ok(UnlinkOutcome::Unlinked)
+        }).await
+    }
+
+    pub async fn remove_all(&self, account_id: &str) -> Result<u64, mongodb::error::Error> {
+        let result = self.collection.delete_many(bson::doc! { "account_id": account_id }).await?;
+
+        Ok(result.deleted_count)
+    }
+}
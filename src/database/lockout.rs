@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use redis::{ AsyncCommands, RedisError };
+
+use crate::database::cache::CacheConnection;
+
+/// Failures allowed before the account starts getting locked.
+const THRESHOLD: u64 = 5;
+
+/// First lock, doubled with every failure after.
+const BASE_DELAY: Duration = Duration::from_secs(30);
+
+const MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Failures are forgotten this long after the last one.
+const FAILURE_MEMORY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long `failures` in a row lock the account for, if at all.
+fn delay(failures: u64) -> Option<Duration> {
+    if failures < THRESHOLD {
+        return None;
+    }
+
+    let doublings = (failures - THRESHOLD).min(16) as u32;
+    Some(BASE_DELAY.saturating_mul(2u32.pow(doublings)).min(MAX_DELAY))
+}
+
+/// Outcome of a failed password.
+pub struct LockoutFailure {
+    /// Set when this failure locked the account for the first time, the owner should know.
+    pub locked: bool,
+}
+
+/// Failed password attempts per account, kept in Redis only.
+#[derive(Clone)]
+pub struct LockoutOperations {
+    cache: CacheConnection,
+}
+
+impl LockoutOperations {
+    pub fn new(cache: CacheConnection) -> Self {
+        LockoutOperations { cache }
+    }
+
+    /// Whether the account is locked right now, passwords shouldn't even be checked if so.
+    pub async fn is_locked(&mut self, account_id: &str) -> Result<bool, RedisError> {
+        self.cache.exists(format!("account:{}:locked", account_id)).await
+    }
+
+    /// Counts a wrong password and locks the account once past `THRESHOLD`, for longer every
+    /// time.
+    pub async fn fail(&mut self, account_id: &str) -> Result<LockoutFailure, RedisError> {
+        let failures_key = format!("account:{}:failures", account_id);
+
+        let (failures,): (u64,) = redis
+            ::pipe()
+            .atomic()
+            .incr(&failures_key, 1)
+            .expire(&failures_key, FAILURE_MEMORY.as_secs() as i64)
+            .ignore()
+            .query_async(&mut self.cache).await?;
+
+        let Some(delay) = delay(failures) else {
+            return Ok(LockoutFailure { locked: false });
+        };
+
+        redis
+            ::cmd("SET")
+            .arg(format!("account:{}:locked", account_id))
+            .arg(true)
+            .arg("PX")
+            .arg(delay.as_millis() as u64)
+            .exec_async(&mut self.cache).await?;
+
+        Ok(LockoutFailure { locked: failures == THRESHOLD })
+    }
+
+    /// A right password clears the slate.
+    pub async fn reset(&mut self, account_id: &str) -> Result<(), RedisError> {
+        self.cache.del::<_, ()>(
+            &[format!("account:{}:failures", account_id), format!("account:{}:locked", account_id)]
+        ).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlocked_below_the_threshold() {
+        for failures in 0..THRESHOLD {
+            assert_eq!(delay(failures), None);
+        }
+    }
+
+    #[test]
+    fn doubles_from_the_threshold() {
+        assert_eq!(delay(THRESHOLD), Some(BASE_DELAY));
+        assert_eq!(delay(THRESHOLD + 1), Some(BASE_DELAY * 2));
+        assert_eq!(delay(THRESHOLD + 3), Some(BASE_DELAY * 8));
+    }
+
+    #[test]
+    fn capped_at_the_max_delay() {
+        // 30s doubled 7 times is past an hour.
+        assert_eq!(delay(THRESHOLD + 6), Some(BASE_DELAY * 64));
+        assert_eq!(delay(THRESHOLD + 7), Some(MAX_DELAY));
+        assert_eq!(delay(u64::MAX), Some(MAX_DELAY));
+    }
+}
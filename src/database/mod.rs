@@ -1,12 +1,30 @@
+use mongodb::{ bson, options::ClientOptions };
+use redis::RedisError;
+
 use crate::{
     database::{
         account::AccountOperations,
         auth::AuthOperations,
+        cache::CacheConnection,
+        authorization_code::AuthorizationCodeOperations,
+        client::ClientOperations,
+        consent::ConsentOperations,
+        dead_letter::DeadLetterOperations,
+        device_code::DeviceCodeOperations,
+        email_code::EmailCodeOperations,
+        grant::GrantOperations,
+        linked_identity::LinkedIdentityOperations,
+        lockout::LockoutOperations,
+        pairwise::PairwiseOperations,
+        personal_token::PersonalTokenOperations,
         partial_login::PartialLoginOperations,
+        mfa_upgrade::MfaUpgradeOperations,
         sudo::SudoOperations,
         totp::{ TotpOperations, code::TotpUsedCodeOperations, store::TotpStoreOperations },
+        upstream_state::UpstreamStateOperations,
     },
     env::{ MONGODB_CONNECTION, REDIS_HOST },
+    utils::telemetry,
 };
 
 pub mod account;
@@ -14,36 +32,85 @@ pub mod totp;
 pub mod auth;
 pub mod sudo;
 pub mod partial_login;
+pub mod mfa_upgrade;
+pub mod client;
+pub mod pairwise;
+pub mod grant;
+pub mod authorization_code;
+pub mod consent;
+pub mod device_code;
+pub mod dead_letter;
+pub mod personal_token;
+pub mod linked_identity;
+pub mod upstream_state;
+pub mod email_code;
+pub mod lockout;
+pub mod cache;
 
 pub struct Database {
+    /// The database itself, for checks that aren't about a single collection.
+    mongo: mongodb::Database,
+    /// Shared Redis connection, for anything that isn't tied to a collection.
+    pub cache: CacheConnection,
     pub account: AccountOperations,
     pub totp: TotpOperations,
     pub auth: AuthOperations,
     pub partial_login: PartialLoginOperations,
+    pub mfa_upgrade: MfaUpgradeOperations,
     pub sudo: SudoOperations,
+    pub client: ClientOperations,
+    pub pairwise: PairwiseOperations,
+    pub grant: GrantOperations,
+    pub authorization_code: AuthorizationCodeOperations,
+    pub consent: ConsentOperations,
+    pub device_code: DeviceCodeOperations,
+    pub dead_letter: DeadLetterOperations,
+    pub personal_token: PersonalTokenOperations,
+    pub linked_identity: LinkedIdentityOperations,
+    pub upstream_state: UpstreamStateOperations,
+    pub email_code: EmailCodeOperations,
+    pub lockout: LockoutOperations,
 }
 
 impl Database {
     pub async fn default() -> Result<Self, mongodb::error::Error> {
         tracing::info!("Connecting to mongodb...");
-        let mongo_client = mongodb::Client::with_uri_str(&*MONGODB_CONNECTION).await.unwrap();
+        let mut mongo_options = ClientOptions::parse(&*MONGODB_CONNECTION).await.unwrap();
+        mongo_options.command_event_handler = telemetry::mongo_command_handler();
+        let mongo_client = mongodb::Client::with_options(mongo_options).unwrap();
         let mongo_database = mongo_client.database("koii");
 
         tracing::info!("Connecting to redis...");
-        let redis_client = redis::Client
-            ::open(&**REDIS_HOST)
-            .unwrap()
-            .get_multiplexed_async_connection().await
-            .unwrap();
+        let redis_client = CacheConnection::new(
+            redis::Client
+                ::open(&**REDIS_HOST)
+                .unwrap()
+                .get_multiplexed_async_connection().await
+                .unwrap()
+        );
 
         let account_collection = mongo_database.collection("account");
         let totp_collection = mongo_database.collection("totp");
         let totp_code_collection = mongo_database.collection("totp_code");
         let auth_collection = mongo_database.collection("auth");
         let partial_login_collection = mongo_database.collection("partial_login");
+        let mfa_upgrade_collection = mongo_database.collection("mfa_upgrade");
         let sudo_collection = mongo_database.collection("sudo");
+        let client_collection = mongo_database.collection("client");
+        let pairwise_collection = mongo_database.collection("pairwise");
+        let grant_collection = mongo_database.collection("grant");
+        let authorization_code_collection = mongo_database.collection("authorization_code");
+        let consent_collection = mongo_database.collection("consent");
+        let device_code_collection = mongo_database.collection("device_code");
+        let dead_letter_collection = mongo_database.collection("dead_letter");
+        let personal_token_collection = mongo_database.collection("personal_token");
+        let linked_identity_collection = mongo_database.collection("linked_identity");
+        let upstream_state_collection = mongo_database.collection("upstream_state");
+        let email_code_collection = mongo_database.collection("email_code");
 
         Ok(Database {
+            mongo: mongo_database.clone(),
+            cache: redis_client.clone(),
             account: AccountOperations::new(account_collection).await.unwrap(),
             totp: TotpOperations {
                 store: TotpStoreOperations::new(
@@ -54,7 +121,38 @@ impl Database {
             },
             auth: AuthOperations::new(auth_collection, redis_client.clone()).await.unwrap(),
             partial_login: PartialLoginOperations::new(partial_login_collection).await.unwrap(),
+            mfa_upgrade: MfaUpgradeOperations::new(mfa_upgrade_collection).await.unwrap(),
             sudo: SudoOperations::new(sudo_collection).await.unwrap(),
+            client: ClientOperations::new(client_collection).await.unwrap(),
+            pairwise: PairwiseOperations::new(pairwise_collection).await.unwrap(),
+            grant: GrantOperations::new(grant_collection, redis_client.clone()).await.unwrap(),
+            authorization_code: AuthorizationCodeOperations::new(
+                authorization_code_collection
+            ).await.unwrap(),
+            consent: ConsentOperations::new(consent_collection).await.unwrap(),
+            device_code: DeviceCodeOperations::new(device_code_collection).await.unwrap(),
+            dead_letter: DeadLetterOperations::new(dead_letter_collection).await.unwrap(),
+            personal_token: PersonalTokenOperations::new(
+                personal_token_collection
+            ).await.unwrap(),
+            linked_identity: LinkedIdentityOperations::new(
+                linked_identity_collection,
+                mongo_client.clone()
+            ).await.unwrap(),
+            upstream_state: UpstreamStateOperations::new(upstream_state_collection).await.unwrap(),
+            email_code: EmailCodeOperations::new(email_code_collection).await.unwrap(),
+            lockout: LockoutOperations::new(redis_client.clone()),
         })
     }
+
+    /// Whether MongoDB answers.
+    pub async fn ping(&self) -> Result<(), mongodb::error::Error> {
+        self.mongo.run_command(bson::doc! { "ping": 1 }).await?;
+        Ok(())
+    }
+
+    /// Whether Redis answers.
+    pub async fn ping_cache(&self) -> Result<(), RedisError> {
+        redis::cmd("PING").exec_async(&mut self.cache.clone()).await
+    }
 }
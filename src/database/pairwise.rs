@@ -0,0 +1,86 @@
+use mongodb::{ Collection, IndexModel, bson, options::IndexOptions };
+use serde::{ Deserialize, Serialize };
+
+/// Records that a client has learned the pairwise subject of an account.
+#[derive(Deserialize, Serialize)]
+pub struct PairwiseDocument {
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    /// The client holding the subject.
+    pub client_id: String,
+
+    /// The subject the client knows the account by.
+    pub subject: String,
+
+    /// The first time the subject was handed to the client.
+    pub issued_at: bson::DateTime,
+}
+
+pub struct PairwiseOperations {
+    collection: Collection<PairwiseDocument>,
+}
+
+impl PairwiseOperations {
+    pub async fn new(
+        collection: Collection<PairwiseDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "account_id": 1, "client_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        Ok(PairwiseOperations { collection })
+    }
+
+    /// Keeps the first issue time if the client already holds the subject.
+    pub async fn record(&self, document: &PairwiseDocument) -> Result<(), mongodb::error::Error> {
+        self.collection
+            .update_one(
+                bson::doc! { "account_id": &document.account_id, "client_id": &document.client_id },
+                bson::doc! {
+                    "$setOnInsert": {
+                        "subject": &document.subject,
+                        "issued_at": document.issued_at
+                    }
+                }
+            )
+            .upsert(true).await?;
+
+        Ok(())
+    }
+
+    pub async fn list(
+        &self,
+        account_id: &str
+    ) -> Result<Vec<PairwiseDocument>, mongodb::error::Error> {
+        let mut cursor = self.collection.find(bson::doc! { "account_id": account_id }).await?;
+
+        let mut documents = Vec::new();
+        while cursor.advance().await? {
+            documents.push(cursor.deserialize_current()?);
+        }
+
+        Ok(documents)
+    }
+
+    pub async fn remove(
+        &self,
+        account_id: &str,
+        client_id: &str
+    ) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.delete_one(
+            bson::doc! { "account_id": account_id, "client_id": client_id }
+        ).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+
+    /// Forgets every subject a client was handed, for when it's deleted.
+    pub async fn remove_client(&self, client_id: &str) -> Result<(), mongodb::error::Error> {
+        self.collection.delete_many(bson::doc! { "client_id": client_id }).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use mongodb::{
+    Collection,
+    IndexModel,
+    bson,
+    error::WriteFailure,
+    options::IndexOptions,
+};
+use serde::{ Deserialize, Serialize };
+
+/// Every personal access token starts with this, so leaked ones are easy to scan for.
+pub const TOKEN_PREFIX: &str = "koii_pat_";
+
+/// Lets a token read what the account endpoints list, nothing that changes the account.
+pub const ACCOUNT_READ_SCOPE: &str = "account:read";
+
+/// `last_used_at` is only moved once this much has passed, not on every request.
+const LAST_USED_PRECISION: Duration = Duration::from_secs(60);
+
+/// A token the user made for scripts, sent as `Authorization: Bearer`.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PersonalTokenDocument {
+    /// Public ID to list and revoke the token with, useless as a credential.
+    pub token_id: String,
+
+    /// Unique ID to the account.
+    pub account_id: String,
+
+    /// Name the user gave the token.
+    pub name: String,
+
+    /// Scopes the token was made for, checked by the API receiving it.
+    pub scopes: Vec<String>,
+
+    /// SHA-256 of the token, the token itself is only shown once.
+    pub token_hash: String,
+
+    /// TTL: the token is gone once this passes, tokens without one live until revoked.
+    pub expires_at: Option<bson::DateTime>,
+
+    pub last_used_at: Option<bson::DateTime>,
+
+    pub issued_at: bson::DateTime,
+}
+
+pub struct PersonalTokenOperations {
+    collection: Collection<PersonalTokenDocument>,
+}
+
+impl PersonalTokenOperations {
+    pub async fn new(
+        collection: Collection<PersonalTokenDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "token_hash": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "account_id": 1, "token_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "expires_at": 1 })
+                .options(IndexOptions::builder().expire_after(Duration::ZERO).build())
+                .build()
+        ).await?;
+
+        Ok(PersonalTokenOperations { collection })
+    }
+
+    pub async fn add(
+        &self,
+        document: &PersonalTokenDocument
+    ) -> Result<bool, mongodb::error::Error> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Finds an unexpired token by its hash and stamps it as used, at most once a minute.
+    pub async fn use_token(
+        &self,
+        token_hash: &str
+    ) -> Result<Option<PersonalTokenDocument>, mongodb::error::Error> {
+        let now = bson::DateTime::now();
+
+        // The TTL monitor only runs every minute, don't trust it with the expiry.
+        let document = self.collection.find_one(
+            bson::doc! {
+                "token_hash": token_hash,
+                "$or": [{ "expires_at": null }, { "expires_at": { "$gt": now } }]
+            }
+        ).await?;
+
+        let Some(mut document) = document else {
+            return Ok(None);
+        };
+
+        let stale = document.last_used_at.is_none_or(|last_used_at| {
+            last_used_at.to_system_time().elapsed().unwrap_or_default() >= LAST_USED_PRECISION
+        });
+
+        if stale {
+            self.collection.update_one(
+                bson::doc! { "token_hash": token_hash },
+                bson::doc! { "$set": { "last_used_at": now } }
+            ).await?;
+            document.last_used_at = Some(now);
+        }
+
+        Ok(Some(document))
+    }
+
+    pub async fn count(&self, account_id: &str) -> Result<u64, mongodb::error::Error> {
+        self.collection.count_documents(bson::doc! { "account_id": account_id }).await
+    }
+
+    pub async fn list(
+        &self,
+        account_id: &str
+    ) -> Result<Vec<PersonalTokenDocument>, mongodb::error::Error> {
+        let mut cursor = self.collection.find(bson::doc! { "account_id": account_id }).await?;
+
+        let mut documents = Vec::new();
+        while cursor.advance().await? {
+            documents.push(cursor.deserialize_current()?);
+        }
+
+        Ok(documents)
+    }
+
+    pub async fn revoke(
+        &self,
+        account_id: &str,
+        token_id: &str
+    ) -> Result<bool, mongodb::error::Error> {
+        let result = self.collection.delete_one(
+            bson::doc! { "account_id": account_id, "token_id": token_id }
+        ).await?;
+
+        Ok(result.deleted_count == 1)
+    }
+
+    pub async fn revoke_all(&self, account_id: &str) -> Result<u64, mongodb::error::Error> {
+        let result = self.collection.delete_many(bson::doc! { "account_id": account_id }).await?;
+
+        Ok(result.deleted_count)
+    }
+}
@@ -0,0 +1,94 @@
+use mongodb::{ Collection, IndexModel, bson, error::WriteFailure, options::IndexOptions };
+use serde::{ Deserialize, Serialize };
+
+use crate::env::UPSTREAM_STATE_MAX_AGE;
+
+/// Everything needed to finish a trip to an upstream identity provider.
+#[derive(Deserialize, Serialize)]
+pub struct UpstreamStateDocument {
+    /// The `state` sent to the provider, also kept in the browser's `upstream_state` cookie.
+    pub state: String,
+
+    pub provider: String,
+
+    /// PKCE verifier, the provider only ever saw its challenge.
+    pub code_verifier: String,
+
+    /// Must come back in the ID token of OpenID Connect providers.
+    pub nonce: String,
+
+    /// Set when a signed in account is linking the identity rather than signing in with it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+
+    /// TTL: UPSTREAM_STATE_MAX_AGE
+    pub issued_at: bson::DateTime,
+}
+
+pub struct UpstreamStateOperations {
+    collection: Collection<UpstreamStateDocument>,
+}
+
+impl UpstreamStateOperations {
+    pub async fn new(
+        collection: Collection<UpstreamStateDocument>
+    ) -> Result<Self, mongodb::error::Error> {
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "state": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build()
+        ).await?;
+
+        collection.create_index(
+            IndexModel::builder()
+                .keys(bson::doc! { "issued_at": 1 })
+                .options(IndexOptions::builder().expire_after(*UPSTREAM_STATE_MAX_AGE).build())
+                .build()
+        ).await?;
+
+        Ok(UpstreamStateOperations { collection })
+    }
+
+    pub async fn add(
+        &self,
+        document: &UpstreamStateDocument
+    ) -> Result<bool, mongodb::error::Error> {
+        match self.collection.insert_one(document).await {
+            Ok(_) => {}
+            Err(error) => {
+                match *error.kind {
+                    mongodb::error::ErrorKind::Write(WriteFailure::WriteError(ref write_error)) if
+                        write_error.code == 11000
+                    => {
+                        return Ok(false);
+                    }
+                    _ => {
+                        return Err(error);
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// A state can only be used once.
+    pub async fn consume(
+        &self,
+        state: &str,
+        provider: &str
+    ) -> Result<Option<UpstreamStateDocument>, mongodb::error::Error> {
+        let document = self.collection.find_one_and_delete(
+            bson::doc! { "state": state, "provider": provider }
+        ).await?;
+
+        // The TTL monitor only runs every minute.
+        Ok(
+            document.filter(|document| {
+                document.issued_at.to_system_time().elapsed().unwrap_or_default() <=
+                    *UPSTREAM_STATE_MAX_AGE
+            })
+        )
+    }
+}
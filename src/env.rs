@@ -1,4 +1,5 @@
 use std::{ net::SocketAddr, sync::LazyLock, time::Duration };
+use ipnet::IpNet;
 use url::Url;
 
 // Any variables inside of this file can only be used AFTER `dotenv::dotenv().ok()`.
@@ -9,15 +10,88 @@ pub const HOST: LazyLock<SocketAddr> = LazyLock::new(||
     get_env_value("HOST").parse::<SocketAddr>().unwrap()
 );
 
+/// Interface for `/readyz` and `/metrics`, apart from `HOST` so they never face the public.
+pub const OPS_HOST: LazyLock<SocketAddr> = LazyLock::new(||
+    get_env_value("OPS_HOST").parse::<SocketAddr>().unwrap()
+);
+
 /// Origin domain is used for CORS and passkey.
 pub const ORIGIN_DOMAIN: LazyLock<Url> = LazyLock::new(||
     Url::parse(&get_env_value("ORIGIN_DOMAIN")).unwrap()
 );
 
+/// Public URL of this server, the issuer identifier of Koii as an OpenID provider.
+pub const ISSUER: LazyLock<String> = LazyLock::new(||
+    get_env_value("ISSUER").trim_end_matches('/').to_string()
+);
+
+/// Frontend page asking the user to approve a client, advertised as the authorization endpoint.
+pub const AUTHORIZE_PAGE: LazyLock<Url> = LazyLock::new(||
+    Url::parse(&get_env_value("AUTHORIZE_PAGE")).unwrap()
+);
+
+/// Frontend page where users type the code shown on their device.
+pub const DEVICE_PAGE: LazyLock<Url> = LazyLock::new(||
+    Url::parse(&get_env_value("DEVICE_PAGE")).unwrap()
+);
+
+/// Frontend page upstream identity providers send the user back to, the provider's name is
+/// appended as the last path segment.
+pub const EXTERNAL_CALLBACK_PAGE: LazyLock<Url> = LazyLock::new(||
+    Url::parse(&get_env_value("EXTERNAL_CALLBACK_PAGE")).unwrap()
+);
+
+/// Comma separated upstream identity providers to offer, each configured through
+/// `UPSTREAM_{NAME}_*` values, see `utils::upstream`.
+pub const UPSTREAM_PROVIDERS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    get_env_value("UPSTREAM_PROVIDERS")
+        .split(',')
+        .map(|provider| provider.trim().to_lowercase())
+        .filter(|provider| !provider.is_empty())
+        .collect()
+});
+
+/// Comma separated CIDRs of the proxies in front of Koii (a load balancer), only their
+/// `X-Forwarded-For` and `Forwarded` headers are believed.
+pub const TRUSTED_PROXIES: LazyLock<Vec<IpNet>> = LazyLock::new(|| {
+    parse_cidrs("TRUSTED_PROXIES", &get_env_value("TRUSTED_PROXIES"))
+});
+
+/// Comma separated CIDRs of Cloudflare, trusted like `TRUSTED_PROXIES` and the only peers whose
+/// `CF-Connecting-IP` is believed.
+pub const CLOUDFLARE_PROXIES: LazyLock<Vec<IpNet>> = LazyLock::new(|| {
+    parse_cidrs("CLOUDFLARE_PROXIES", &std::env::var("CLOUDFLARE_PROXIES").unwrap_or_default())
+});
+
 // File path for SSL when hosting in secure context.
 pub const SSL_CERT: LazyLock<String> = LazyLock::new(|| get_env_value("SSL_CERT"));
 pub const SSL_KEY: LazyLock<String> = LazyLock::new(|| get_env_value("SSL_KEY"));
 
+/// CA bundle client certificates are checked against in secure context, left empty to accept
+/// any client.
+pub const CLIENT_CA_BUNDLE: LazyLock<Option<String>> = LazyLock::new(|| {
+    std::env::var("CLIENT_CA_BUNDLE").ok().filter(|bundle| !bundle.is_empty())
+});
+
+/// `json` for one JSON object per line, plain text otherwise.
+pub const LOG_JSON: LazyLock<bool> = LazyLock::new(|| {
+    std::env::var("LOG_FORMAT").is_ok_and(|format| format == "json")
+});
+/// Salt to hash personal data in logs with, it's masked when there's none.
+pub const LOG_SALT: LazyLock<Option<String>> = LazyLock::new(|| {
+    std::env::var("LOG_SALT").ok().filter(|salt| !salt.is_empty())
+});
+
+/// `memory` keeps rate limit counters in the process for tests, they're in Redis otherwise.
+pub const RATE_LIMIT_MEMORY: LazyLock<bool> = LazyLock::new(|| {
+    std::env::var("RATE_LIMIT_STORE").is_ok_and(|store| store == "memory")
+});
+
+/// OpenTelemetry collector to export traces to, over OTLP/HTTP.
+pub const OTLP_ENDPOINT: LazyLock<Option<String>> = LazyLock::new(|| {
+    std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok().filter(|endpoint| !endpoint.is_empty())
+});
+
 // File path for jsonwebtoken to encrypt in ES256.
 pub const JWT_PUBLIC: LazyLock<String> = LazyLock::new(|| get_env_value("JWT_PUBLIC"));
 pub const JWT_PRIVATE: LazyLock<String> = LazyLock::new(|| get_env_value("JWT_PRIVATE"));
@@ -32,6 +106,36 @@ pub const REDIS_HOST: LazyLock<String> = LazyLock::new(|| get_env_value("REDIS_H
 pub const TURNSTILE_SECRET: LazyLock<String> = LazyLock::new(|| get_env_value("TURNSTILE_SECRET"));
 pub const RESEND_TOKEN: LazyLock<String> = LazyLock::new(|| get_env_value("RESEND_TOKEN"));
 
+// Keyed hash secrets.
+pub const PAIRWISE_SECRET: LazyLock<String> = LazyLock::new(|| get_env_value("PAIRWISE_SECRET"));
+
+/// Initial access token for dynamic client registration, handed to the tooling that sets up
+/// preview environments.
+pub const REGISTRATION_TOKEN: LazyLock<String> = LazyLock::new(||
+    get_env_value("REGISTRATION_TOKEN")
+);
+
+/// Scopes a dynamically registered client may ask for, space separated. Clients registered by
+/// hand aren't held to it.
+pub const REGISTRATION_SCOPES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    get_env_value("REGISTRATION_SCOPES")
+        .split(' ')
+        .filter(|scope| !scope.is_empty())
+        .map(|scope| scope.to_string())
+        .collect()
+});
+
+/// Scopes personal access tokens may carry besides `account:read`, space separated. Resource
+/// servers check them through `/oauth/introspect`.
+pub const PERSONAL_TOKEN_SCOPES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    std::env::var("PERSONAL_TOKEN_SCOPES")
+        .unwrap_or_default()
+        .split(' ')
+        .filter(|scope| !scope.is_empty())
+        .map(|scope| scope.to_string())
+        .collect()
+});
+
 // Time based configs.
 pub const TOKEN_MAX_AGE: LazyLock<Duration> = LazyLock::new(|| secs_from_env("TOKEN_MAX_AGE"));
 pub const REFRESH_MAX_AGE: LazyLock<Duration> = LazyLock::new(|| secs_from_env("REFRESH_MAX_AGE"));
@@ -51,6 +155,25 @@ pub const ACCOUNT_DELETE_WINDOW: LazyLock<Duration> = LazyLock::new(||
 pub const TOTP_CODE_VOID_WINDOW: LazyLock<Duration> = LazyLock::new(||
     secs_from_env("TOTP_CODE_VOID_WINDOW")
 );
+pub const OAUTH_CODE_MAX_AGE: LazyLock<Duration> = LazyLock::new(||
+    secs_from_env("OAUTH_CODE_MAX_AGE")
+);
+pub const DEVICE_CODE_MAX_AGE: LazyLock<Duration> = LazyLock::new(||
+    secs_from_env("DEVICE_CODE_MAX_AGE")
+);
+pub const DEVICE_POLL_INTERVAL: LazyLock<Duration> = LazyLock::new(||
+    secs_from_env("DEVICE_POLL_INTERVAL")
+);
+pub const UPSTREAM_STATE_MAX_AGE: LazyLock<Duration> = LazyLock::new(||
+    secs_from_env("UPSTREAM_STATE_MAX_AGE")
+);
+pub const EMAIL_CODE_MAX_AGE: LazyLock<Duration> = LazyLock::new(||
+    secs_from_env("EMAIL_CODE_MAX_AGE")
+);
+/// How long in-flight requests, then queued worker jobs, get to finish on shutdown, together.
+pub const SHUTDOWN_TIMEOUT: LazyLock<Duration> = LazyLock::new(||
+    secs_from_env("SHUTDOWN_TIMEOUT")
+);
 pub const EMAIL_BATCHING_WINDOW: LazyLock<Duration> = LazyLock::new(||
     secs_from_env("EMAIL_BATCHING_WINDOW")
 );
@@ -79,14 +202,44 @@ pub const ACCOUNT_TOKEN_IDENTIFIER_LENGTH: LazyLock<usize> = LazyLock::new(||
 pub const EMAIL_VERIFY_CODE_LENGTH: LazyLock<usize> = LazyLock::new(||
     parse_env_number("EMAIL_VERIFY_CODE_LENGTH")
 );
+pub const OAUTH_CODE_LENGTH: LazyLock<usize> = LazyLock::new(||
+    parse_env_number("OAUTH_CODE_LENGTH")
+);
+pub const CLIENT_ID_LENGTH: LazyLock<usize> = LazyLock::new(||
+    parse_env_number("CLIENT_ID_LENGTH")
+);
+pub const CLIENT_SECRET_LENGTH: LazyLock<usize> = LazyLock::new(||
+    parse_env_number("CLIENT_SECRET_LENGTH")
+);
+pub const PERSONAL_TOKEN_LENGTH: LazyLock<usize> = LazyLock::new(||
+    parse_env_number("PERSONAL_TOKEN_LENGTH")
+);
 pub const TOTP_SECRET_LENGTH: LazyLock<usize> = LazyLock::new(||
     parse_env_number("TOTP_SECRET_LENGTH")
 );
 
+/// Reads `UPSTREAM_{PROVIDER}_{KEY}`, most provider settings have defaults so this doesn't panic.
+pub fn upstream_value(provider: &str, key: &str) -> Option<String> {
+    std::env::var(format!("UPSTREAM_{}_{}", provider.to_uppercase(), key))
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
 fn get_env_value(key: &str) -> String {
     std::env::var(key).expect(&format!("{key} must be set in .env file."))
 }
 
+fn parse_cidrs(key: &str, value: &str) -> Vec<IpNet> {
+    value
+        .split(',')
+        .map(|cidr| cidr.trim())
+        .filter(|cidr| !cidr.is_empty())
+        .map(|cidr| {
+            cidr.parse::<IpNet>().unwrap_or_else(|_| panic!("{key} must be a list of CIDRs."))
+        })
+        .collect()
+}
+
 fn parse_env_number(key: &str) -> usize {
     get_env_value(key).parse::<usize>().unwrap()
 }
@@ -1,4 +1,4 @@
-use std::{ env::args, sync::Arc, time::Instant };
+use std::{ env::args, net::SocketAddr, sync::Arc, time::Instant };
 
 use axum::{
     Router,
@@ -6,14 +6,33 @@ use axum::{
     http::{ HeaderValue, Method, header::{ AUTHORIZATION, CONTENT_TYPE } },
     middleware,
 };
-use axum_server::tls_rustls::RustlsConfig;
+use axum_server::{ Handle, tls_rustls::RustlsConfig };
 use tower_http::cors::CorsLayer;
+use tracing_subscriber::{ filter::LevelFilter, layer::SubscriberExt, util::SubscriberInitExt };
 use crate::{
     database::Database,
-    env::{ HOST, ORIGIN_DOMAIN, SSL_CERT, SSL_KEY },
-    middlewares::track,
+    env::{
+        CLIENT_CA_BUNDLE,
+        HOST,
+        LOG_JSON,
+        ORIGIN_DOMAIN,
+        OPS_HOST,
+        OTLP_ENDPOINT,
+        RATE_LIMIT_MEMORY,
+    },
+    middlewares::{ client_ip, rate_limit::RateLimiter, track },
     routes::ily,
-    utils::{ jwt::JwtService, passkey::PasskeyService, turnstile::Turnstile },
+    utils::{
+        jwt::JwtService,
+        metrics,
+        passkey::PasskeyService,
+        reload,
+        shutdown,
+        telemetry,
+        tls,
+        turnstile::Turnstile,
+        upstream::Upstream,
+    },
     workers::{ WorkerSpec, Workers, WorkersAllocate },
 };
 
@@ -31,13 +50,26 @@ pub struct AppState {
     pub jwt: JwtService,
     pub passkey: PasskeyService,
     pub turnstile: Turnstile,
+    pub upstream: Upstream,
+    pub rate_limit: RateLimiter,
     pub debug: bool,
 }
 
 /// Must be called first for any interaction with the router.
 pub fn init() {
     dotenv::dotenv().ok();
-    tracing_subscriber::fmt().init();
+    tracing_subscriber
+        ::registry()
+        .with((!*LOG_JSON).then(tracing_subscriber::fmt::layer))
+        .with(LOG_JSON.then(|| tracing_subscriber::fmt::layer().json()))
+        .with(telemetry::layer())
+        .with(LevelFilter::INFO)
+        .init();
+    metrics::install();
+
+    if let Some(endpoint) = &*OTLP_ENDPOINT {
+        tracing::info!("Exporting traces to {}.", endpoint);
+    }
     rustls::crypto::ring::default_provider().install_default().unwrap();
 }
 
@@ -54,14 +86,25 @@ pub async fn core() {
     match mode[1].as_str() {
         "secure" => {
             tracing::info!("Serving in secure context...");
-            let tls_config = RustlsConfig::from_pem_file(
-                SSL_CERT.clone(),
-                SSL_KEY.clone()
-            ).await.unwrap();
+            if CLIENT_CA_BUNDLE.is_none() {
+                tracing::warn!("No CLIENT_CA_BUNDLE set, anyone can connect to the origin.");
+            }
+
+            let tls_config = RustlsConfig::from_config(tls::server_config().await.unwrap());
+            let app_state = state(false).await;
+            reload::on_hangup(app_state.clone(), Some(tls_config.clone()));
+            let ops = serve_ops(app_state.clone());
+
             axum_server
                 ::bind_rustls(*HOST, tls_config)
-                .serve(app(false).await.into_make_service()).await
+                .handle(shutdown::on_terminate())
+                .serve(
+                    router(app_state.clone()).into_make_service_with_connect_info::<SocketAddr>()
+                ).await
                 .unwrap();
+
+            ops.shutdown();
+            app_state.worker.drain(shutdown::remaining()).await;
         }
         "insecure" => {
             tracing::info!("Serving in insecure context...");
@@ -71,19 +114,44 @@ pub async fn core() {
             tracing::info!(
                 "Disabled security features:\n- mSSL to communicate with Cloudflare.\n- Turnstile check."
             );
-            axum_server::bind(*HOST).serve(app(true).await.into_make_service()).await.unwrap();
+            let app_state = state(true).await;
+            reload::on_hangup(app_state.clone(), None);
+            let ops = serve_ops(app_state.clone());
+
+            axum_server
+                ::bind(*HOST)
+                .handle(shutdown::on_terminate())
+                .serve(
+                    router(app_state.clone()).into_make_service_with_connect_info::<SocketAddr>()
+                ).await
+                .unwrap();
+
+            ops.shutdown();
+            app_state.worker.drain(shutdown::remaining()).await;
+        }
+        _ => {
+            tracing::error!("No context chosen, shutting down... [secure/insecure]");
+            return;
         }
-        _ => tracing::error!("No context chosen, shutting down... [secure/insecure]"),
     }
+
+    telemetry::shutdown();
+    tracing::info!("Goodbye, world! :3");
 }
 
 /// Creates an app.
 pub async fn app(debug: bool) -> Router {
+    router(state(debug).await)
+}
+
+/// Connects to everything the app needs and starts the workers.
+pub async fn state(debug: bool) -> Arc<AppState> {
     tracing::info!("Initializing server state...");
     let boot_time = Instant::now();
 
-    let app_state = Arc::new(AppState {
-        worker: Workers::new(WorkersAllocate {
+    let db = Database::default().await.unwrap();
+    let worker = Workers::new(
+        WorkersAllocate {
             hash_pass: WorkerSpec {
                 threads: 12,
                 buffer: 2048,
@@ -92,18 +160,63 @@ pub async fn app(debug: bool) -> Router {
                 threads: 12,
                 buffer: 2048,
             },
-            verify_email: WorkerSpec {
+            email: WorkerSpec {
                 threads: 1,
                 buffer: 100,
             },
-        }),
-        db: Database::default().await.unwrap(),
+            backchannel_logout: WorkerSpec {
+                threads: 4,
+                buffer: 1024,
+            },
+        },
+        db.dead_letter.clone()
+    );
+
+    let rate_limit = match *RATE_LIMIT_MEMORY {
+        true => RateLimiter::memory(),
+        false => RateLimiter::redis(db.cache.clone()),
+    };
+
+    let app_state = Arc::new(AppState {
+        worker,
+        db,
         jwt: JwtService::new(),
         passkey: PasskeyService::new(),
         turnstile: Turnstile::default(),
+        upstream: Upstream::load(),
+        rate_limit,
         debug,
     });
 
+    tracing::info!(
+        "Server started succesfully. (Boot time: {}ms)",
+        boot_time.elapsed().as_millis()
+    );
+
+    app_state
+}
+
+/// Readiness and metrics over an existing state, see `OPS_HOST`.
+pub fn ops_router(app_state: Arc<AppState>) -> Router {
+    routes::health::ops_routes(app_state)
+}
+
+/// Serves `ops_router` on `OPS_HOST` until the returned handle is shut down.
+fn serve_ops(app_state: Arc<AppState>) -> Handle<SocketAddr> {
+    let handle = Handle::new();
+    let server = axum_server::bind(*OPS_HOST).handle(handle.clone());
+
+    tokio::spawn(async move {
+        if let Err(error) = server.serve(ops_router(app_state).into_make_service()).await {
+            tracing::error!("Unable to serve on OPS_HOST: {}", error);
+        }
+    });
+
+    handle
+}
+
+/// Routes of the app over an existing state.
+pub fn router(app_state: Arc<AppState>) -> Router {
     let cors = CorsLayer::new()
         .allow_origin(
             tower_http::cors::AllowOrigin::predicate(|origin: &HeaderValue, _| {
@@ -115,17 +228,19 @@ pub async fn app(debug: bool) -> Router {
         )
         .allow_methods(vec![Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
         .allow_headers([AUTHORIZATION, CONTENT_TYPE])
+        .expose_headers([track::REQUEST_ID])
         .allow_credentials(true);
 
-    tracing::info!(
-        "Server started succesfully. (Boot time: {}ms)",
-        boot_time.elapsed().as_millis()
-    );
-
     Router::new()
         .nest("/account", routes::account::routes(app_state.clone()))
+        .nest("/oauth", routes::oauth::routes(app_state.clone()))
+        .nest("/.well-known", routes::well_known::routes(app_state.clone()))
+        .merge(routes::health::routes())
         .route("/ily", axum::routing::get(ily::handler))
+        .layer(middleware::from_fn(track::measure))
         .layer(middleware::from_fn(track::log_requests))
+        .layer(middleware::from_fn(track::trace))
+        .layer(middleware::from_fn(client_ip::resolve))
         .layer(DefaultBodyLimit::max(1 * 1024 * 1024))
         .layer(cors)
 }
@@ -12,8 +12,11 @@ use reqwest::header::COOKIE;
 use crate::{
     AppState,
     base,
-    database::auth::AuthOperationError,
-    utils::jwt::{ KeyClaims, KeyKind },
+    database::{
+        auth::AuthOperationError,
+        personal_token::{ PersonalTokenDocument, TOKEN_PREFIX },
+    },
+    utils::{ jwt::{ KeyClaims, KeyKind }, oauth::{ bearer_token, hash_token }, redact },
 };
 
 #[derive(Clone)]
@@ -22,6 +25,24 @@ pub struct AuthorizationInfo {
     pub active: bool,
     pub token: Option<KeyClaims>,
     pub refresh: Option<KeyClaims>,
+    /// Set when a personal access token came as `Authorization: Bearer`.
+    ///
+    /// Kept apart from `token` so scripts only reach the endpoints that ask for it.
+    pub personal: Option<PersonalTokenDocument>,
+}
+
+impl AuthorizationInfo {
+    /// The signed in account, or the account of a personal access token granted `scope`.
+    pub fn account_id(&self, scope: &str) -> Option<&str> {
+        if let Some(token) = &self.token {
+            return Some(&token.account_id);
+        }
+
+        self.personal
+            .as_ref()
+            .filter(|personal| personal.scopes.iter().any(|granted| granted == scope))
+            .map(|personal| personal.account_id.as_str())
+    }
 }
 
 pub async fn authorize(
@@ -34,8 +55,26 @@ pub async fn authorize(
         active: false,
         token: None,
         refresh: None,
+        personal: None,
     };
 
+    // A request with a bearer token is judged on it alone, cookies the browser attached on
+    // its own never get to add anything.
+    if let Some(bearer) = bearer_token(&headers) {
+        let info = match bearer.starts_with(TOKEN_PREFIX) {
+            true => {
+                AuthorizationInfo {
+                    personal: parse_personal_token(&state, bearer).await,
+                    ..failed
+                }
+            }
+            false => parse_bearer(&state, bearer).await,
+        };
+
+        request.extensions_mut().insert(info);
+        return next.run(request).await;
+    }
+
     let cookies = match headers.get(COOKIE) {
         Some(cookies) => cookies,
         None => {
@@ -76,6 +115,7 @@ async fn parse_cookies(
             active: false,
             token: None,
             refresh: None,
+            personal: None,
         });
     };
 
@@ -92,7 +132,7 @@ async fn parse_cookies(
             Err(error) => {
                 tracing::error!(
                     "Failed to query database for token `{}`: {error}",
-                    payload.value()
+                    redact::Token(payload.value())
                 );
             }
         }
@@ -111,7 +151,7 @@ async fn parse_cookies(
             Err(error) => {
                 tracing::error!(
                     "Failed to query database for refresh `{}`: {error}",
-                    payload.value()
+                    redact::Token(payload.value())
                 );
             }
         }
@@ -121,5 +161,43 @@ async fn parse_cookies(
         active,
         token,
         refresh,
+        personal: None,
     })
 }
+
+/// Same checks as the `token` cookie, for clients that can't keep cookies.
+async fn parse_bearer(state: &AppState, bearer: &str) -> AuthorizationInfo {
+    let mut token = None;
+
+    if let Some(claims) = state.jwt.verify(bearer, KeyKind::Authentication) {
+        match state.db.auth.clone().check_token(&claims).await {
+            Ok(true) => {
+                token = Some(claims);
+            }
+            Ok(false) => {} // The token is revoked, don't add anything.
+            Err(error) => {
+                tracing::error!(
+                    "Failed to query database for bearer token `{}`: {error}",
+                    claims.identifier
+                );
+            }
+        }
+    }
+
+    AuthorizationInfo {
+        active: token.is_some(),
+        token,
+        refresh: None,
+        personal: None,
+    }
+}
+
+async fn parse_personal_token(state: &AppState, bearer: &str) -> Option<PersonalTokenDocument> {
+    match state.db.personal_token.use_token(&hash_token(bearer)).await {
+        Ok(personal) => personal,
+        Err(error) => {
+            tracing::error!("Failed to query database for a personal access token: {error}");
+            None
+        }
+    }
+}
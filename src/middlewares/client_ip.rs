@@ -0,0 +1,162 @@
+use std::net::{ IpAddr, SocketAddr };
+
+use axum::{
+    extract::{ ConnectInfo, Request },
+    http::HeaderMap,
+    middleware::Next,
+    response::IntoResponse,
+};
+
+use crate::env::{ CLOUDFLARE_PROXIES, TRUSTED_PROXIES };
+
+/// The address of whoever is really on the other end, in the extensions of every request.
+///
+/// Missing only when there's no socket at all, like in tests.
+#[derive(Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+pub async fn resolve(mut request: Request, next: Next) -> impl IntoResponse {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip().to_canonical());
+
+    if let Some(peer) = peer {
+        let client = match is_trusted(&peer) {
+            true => from_headers(request.headers(), is_cloudflare(&peer)).unwrap_or(peer),
+            false => peer,
+        };
+
+        request.extensions_mut().insert(ClientIp(client));
+    }
+
+    next.run(request).await
+}
+
+/// IPv4-mapped IPv6 addresses from a dual-stack socket count as the IPv4 they carry.
+fn is_trusted(ip: &IpAddr) -> bool {
+    let ip = ip.to_canonical();
+    is_cloudflare(&ip) || TRUSTED_PROXIES.iter().any(|cidr| cidr.contains(&ip))
+}
+
+fn is_cloudflare(ip: &IpAddr) -> bool {
+    CLOUDFLARE_PROXIES.iter().any(|cidr| cidr.contains(ip))
+}
+
+/// Only called for trusted peers, anyone else could write whatever they want in these.
+fn from_headers(headers: &HeaderMap, cloudflare: bool) -> Option<IpAddr> {
+    // Cloudflare overwrites this one, it's never the client's own. Another proxy could pass
+    // it through from the client as is.
+    if
+        cloudflare &&
+        let Some(ip) = headers
+            .get("cf-connecting-ip")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok())
+    {
+        return Some(ip);
+    }
+
+    let forwarded_for: Vec<IpAddr> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .collect();
+    if let Some(ip) = rightmost_untrusted(&forwarded_for, is_trusted) {
+        return Some(ip);
+    }
+
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("forwarded")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(forwarded_for_node)
+        .collect();
+    rightmost_untrusted(&forwarded, is_trusted)
+}
+
+/// Proxies append to the chain, so the first untrusted hop from the right is the last one a
+/// trusted proxy saw. Anything left of it came from the client and can be forged.
+fn rightmost_untrusted(chain: &[IpAddr], is_trusted: fn(&IpAddr) -> bool) -> Option<IpAddr> {
+    chain
+        .iter()
+        .rev()
+        .find(|ip| !is_trusted(ip))
+        .or(chain.first())
+        .copied()
+}
+
+/// The `for=` node of one RFC 7239 element, like `for=192.0.2.60`, `for="[2001:db8::1]:443"`.
+fn forwarded_for_node(element: &str) -> Option<IpAddr> {
+    let node = element.split(';').find_map(|pair| {
+        let (key, value) = pair.trim().split_once('=')?;
+        key.eq_ignore_ascii_case("for").then_some(value.trim().trim_matches('"'))
+    })?;
+
+    if let Some(rest) = node.strip_prefix('[') {
+        return rest.split_once(']')?.0.parse::<IpAddr>().ok();
+    }
+
+    // A bare IPv4 may come with a port, obfuscated identifiers like `_hidden` don't parse.
+    node.split(':').next()?.parse::<IpAddr>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn chain(ips: &[&str]) -> Vec<IpAddr> {
+        ips.iter().map(|address| ip(address)).collect()
+    }
+
+    /// Stands in for `TRUSTED_PROXIES="10.0.0.0/8"`.
+    fn private_proxies(ip: &IpAddr) -> bool {
+        matches!(ip, IpAddr::V4(ip) if ip.octets()[0] == 10)
+    }
+
+    #[test]
+    fn rightmost_untrusted_skips_trusted_hops() {
+        let hops = chain(&["203.0.113.9", "198.51.100.7", "10.0.0.2", "10.0.0.1"]);
+        assert_eq!(rightmost_untrusted(&hops, private_proxies), Some(ip("198.51.100.7")));
+    }
+
+    #[test]
+    fn rightmost_untrusted_ignores_forged_hops() {
+        // The client made up everything left of what the first proxy saw.
+        let hops = chain(&["10.0.0.5", "192.0.2.1", "198.51.100.7", "10.0.0.1"]);
+        assert_eq!(rightmost_untrusted(&hops, private_proxies), Some(ip("198.51.100.7")));
+    }
+
+    #[test]
+    fn rightmost_untrusted_falls_back_to_the_first_hop() {
+        let hops = chain(&["10.0.0.3", "10.0.0.2"]);
+        assert_eq!(rightmost_untrusted(&hops, private_proxies), Some(ip("10.0.0.3")));
+        assert_eq!(rightmost_untrusted(&[], private_proxies), None);
+    }
+
+    #[test]
+    fn forwarded_for_node_reads_every_form() {
+        assert_eq!(forwarded_for_node("for=192.0.2.60"), Some(ip("192.0.2.60")));
+        assert_eq!(forwarded_for_node("For=\"192.0.2.60:8080\""), Some(ip("192.0.2.60")));
+        assert_eq!(
+            forwarded_for_node("proto=https; for=\"[2001:db8::1]:443\";by=203.0.113.43"),
+            Some(ip("2001:db8::1"))
+        );
+        assert_eq!(forwarded_for_node("for=\"[2001:db8::1]\""), Some(ip("2001:db8::1")));
+    }
+
+    #[test]
+    fn forwarded_for_node_skips_what_isnt_an_address() {
+        assert_eq!(forwarded_for_node("for=_hidden"), None);
+        assert_eq!(forwarded_for_node("for=unknown"), None);
+        assert_eq!(forwarded_for_node("by=203.0.113.43"), None);
+        assert_eq!(forwarded_for_node("for=\"[2001:db8::1\""), None);
+    }
+}
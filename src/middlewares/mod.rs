@@ -1,3 +1,5 @@
 pub mod auth;
 pub mod time;
 pub mod track;
+pub mod rate_limit;
+pub mod client_ip;
@@ -0,0 +1,363 @@
+use std::{ collections::HashMap, sync::{ Arc, Mutex }, time::Duration };
+
+use axum::{
+    body::{ self, Body },
+    extract::{ Request, State },
+    http::{ StatusCode, header::RETRY_AFTER },
+    middleware::Next,
+    response::{ AppendHeaders, IntoResponse },
+};
+use redis::{ RedisError, Script };
+use serde::Deserialize;
+use url::form_urlencoded;
+
+use crate::{
+    AppState,
+    base,
+    database::cache::CacheConnection,
+    middlewares::{ auth::AuthorizationInfo, client_ip::ClientIp },
+    utils::{ oauth::{ basic_credentials, hash_token }, timestamp },
+};
+
+/// What a limit counts requests by.
+#[derive(Clone, Copy)]
+pub enum RateLimitKey {
+    /// The client's address.
+    Ip,
+    /// The signed in account, skipped for anonymous requests.
+    Account,
+    /// The `email` field of the JSON body, skipped when there is none.
+    Email,
+    /// The OAuth client, from `client_secret_basic` or the `client_id` form field, skipped when
+    /// there is none.
+    Client,
+}
+
+impl RateLimitKey {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitKey::Ip => "ip",
+            RateLimitKey::Account => "account",
+            RateLimitKey::Email => "email",
+            RateLimitKey::Client => "client",
+        }
+    }
+}
+
+/// `limit` requests per `window`, a full burst is allowed and then one every `window / limit`.
+pub struct RateLimitRule {
+    pub key: RateLimitKey,
+    pub limit: u32,
+    pub window: Duration,
+}
+
+/// Every rule has to pass for the request to go through.
+pub struct RateLimitPolicy {
+    pub name: &'static str,
+    pub rules: &'static [RateLimitRule],
+}
+
+pub static SIGNUP: RateLimitPolicy = RateLimitPolicy {
+    name: "signup",
+    rules: &[RateLimitRule { key: RateLimitKey::Ip, limit: 10, window: hours(3) }],
+};
+
+pub static LOGIN: RateLimitPolicy = RateLimitPolicy {
+    name: "login",
+    rules: &[RateLimitRule { key: RateLimitKey::Ip, limit: 20, window: hours(2) }],
+};
+
+/// Each request sends an email, so the inbox gets its own limit too.
+pub static EMAIL_LOGIN: RateLimitPolicy = RateLimitPolicy {
+    name: "email_login",
+    rules: &[
+        RateLimitRule { key: RateLimitKey::Ip, limit: 20, window: hours(2) },
+        RateLimitRule { key: RateLimitKey::Email, limit: 5, window: hours(1) },
+    ],
+};
+
+pub static SUDO_EMAIL: RateLimitPolicy = RateLimitPolicy {
+    name: "sudo_email",
+    rules: &[RateLimitRule { key: RateLimitKey::Account, limit: 5, window: hours(1) }],
+};
+
+/// Emailed codes are 6 digits, guessing one has to take far longer than it stays valid.
+pub static EMAIL_REDEEM: RateLimitPolicy = RateLimitPolicy {
+    name: "email_redeem",
+    rules: &[
+        RateLimitRule { key: RateLimitKey::Ip, limit: 30, window: hours(1) },
+        RateLimitRule { key: RateLimitKey::Email, limit: 10, window: hours(1) },
+    ],
+};
+
+pub static SUDO_CONFIRM: RateLimitPolicy = RateLimitPolicy {
+    name: "sudo_confirm",
+    rules: &[
+        RateLimitRule { key: RateLimitKey::Ip, limit: 30, window: hours(1) },
+        RateLimitRule { key: RateLimitKey::Account, limit: 10, window: hours(1) },
+    ],
+};
+
+/// Looking up and approving a user code share the counters, both tell whether a code exists.
+pub static DEVICE: RateLimitPolicy = RateLimitPolicy {
+    name: "device",
+    rules: &[
+        RateLimitRule { key: RateLimitKey::Ip, limit: 30, window: hours(1) },
+        RateLimitRule { key: RateLimitKey::Account, limit: 20, window: hours(1) },
+    ],
+};
+
+/// Loose enough for a device polling every 5 seconds and busy clients refreshing tokens.
+pub static TOKEN: RateLimitPolicy = RateLimitPolicy {
+    name: "token",
+    rules: &[
+        RateLimitRule { key: RateLimitKey::Ip, limit: 600, window: hours(1) },
+        RateLimitRule { key: RateLimitKey::Client, limit: 6000, window: hours(1) },
+    ],
+};
+
+const fn hours(hours: u64) -> Duration {
+    Duration::from_secs(hours * 60 * 60)
+}
+
+/// GCRA over a single key holding the theoretical arrival time, in milliseconds.
+///
+/// Returns how long until the request would be allowed, 0 when it is.
+const GCRA_SCRIPT: &str =
+    r#"
+local now = tonumber(ARGV[1])
+local interval = tonumber(ARGV[2])
+local window = tonumber(ARGV[3])
+local tat = math.max(tonumber(redis.call("GET", KEYS[1]) or now), now)
+local allow_at = tat + interval - window
+if now < allow_at then
+    return allow_at - now
+end
+redis.call("SET", KEYS[1], tat + interval, "PX", tat + interval - now)
+return 0
+"#;
+
+/// Where the counters live, Redis so every instance shares them.
+///
+/// The in-memory store is for tests and local development, it forgets on restart and isn't
+/// shared between instances, see `RATE_LIMIT_STORE`.
+pub enum RateLimiter {
+    Redis(CacheConnection),
+    Memory(Mutex<HashMap<String, Duration>>),
+}
+
+impl RateLimiter {
+    pub fn redis(cache: CacheConnection) -> Self {
+        RateLimiter::Redis(cache)
+    }
+
+    pub fn memory() -> Self {
+        RateLimiter::Memory(Mutex::new(HashMap::new()))
+    }
+
+    /// `Some` with how long to wait when the request is over the limit.
+    pub async fn check(
+        &self,
+        key: &str,
+        rule: &RateLimitRule
+    ) -> Result<Option<Duration>, RedisError> {
+        let now = timestamp::now();
+        let interval = rule.window / rule.limit;
+
+        let wait = match self {
+            RateLimiter::Redis(cache) => {
+                let wait: u64 = Script::new(GCRA_SCRIPT)
+                    .key(key)
+                    .arg(now.as_millis() as u64)
+                    .arg(interval.as_millis() as u64)
+                    .arg(rule.window.as_millis() as u64)
+                    .invoke_async(&mut cache.clone()).await?;
+
+                Duration::from_millis(wait)
+            }
+            RateLimiter::Memory(store) => {
+                let mut store = store.lock().unwrap();
+
+                // Keys past their arrival time are back to a full burst, no need to keep them.
+                if store.len() > 10_000 {
+                    store.retain(|_, tat| *tat > now);
+                }
+
+                match gcra(store.get(key).copied(), now, interval, rule.window) {
+                    Ok(tat) => {
+                        store.insert(key.to_string(), tat);
+                        Duration::ZERO
+                    }
+                    Err(wait) => wait,
+                }
+            }
+        };
+
+        Ok((!wait.is_zero()).then_some(wait))
+    }
+}
+
+/// `GCRA_SCRIPT` for the in-memory store, `Ok` with the new arrival time when the request is
+/// allowed, `Err` with how long to wait otherwise.
+fn gcra(
+    tat: Option<Duration>,
+    now: Duration,
+    interval: Duration,
+    window: Duration
+) -> Result<Duration, Duration> {
+    let tat = tat.unwrap_or(now).max(now);
+    let allow_at = (tat + interval).saturating_sub(window);
+
+    match now < allow_at {
+        true => Err(allow_at - now),
+        false => Ok(tat + interval),
+    }
+}
+
+/// Same as the `DefaultBodyLimit` of the app.
+const MAX_BODY: usize = 1024 * 1024;
+
+#[derive(Deserialize)]
+struct EmailField {
+    email: String,
+}
+
+/// Applies a policy to a route, goes inside `auth::authorize` so accounts are known.
+///
+/// A broken Redis lets requests through, an outage shouldn't lock everyone out.
+pub async fn limit(
+    State((state, policy)): State<(Arc<AppState>, &'static RateLimitPolicy)>,
+    request: Request,
+    next: Next
+) -> impl IntoResponse {
+    let ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| ip.to_string());
+    let account_id = request
+        .extensions()
+        .get::<AuthorizationInfo>()
+        .and_then(|info| info.token.as_ref())
+        .map(|token| token.account_id.clone());
+    let mut client_id = basic_credentials(request.headers()).map(|(client_id, _)| client_id);
+
+    // Only read the body when a rule needs it, and hand it back to the handler after.
+    let needs_body = policy.rules.iter().any(|rule| {
+        match rule.key {
+            RateLimitKey::Email => true,
+            RateLimitKey::Client => client_id.is_none(),
+            _ => false,
+        }
+    });
+
+    let mut request = request;
+    let mut email = None;
+    if needs_body {
+        let (parts, body) = request.into_parts();
+        let Ok(bytes) = body::to_bytes(body, MAX_BODY).await else {
+            return base::response
+                ::error::<u8>(StatusCode::BAD_REQUEST, "Unreadable request body.", None)
+                .into_response();
+        };
+
+        email = serde_json
+            ::from_slice::<EmailField>(&bytes)
+            .ok()
+            .map(|field| field.email.trim().to_lowercase());
+        if client_id.is_none() {
+            client_id = form_urlencoded
+                ::parse(&bytes)
+                .find(|(name, _)| name == "client_id")
+                .map(|(_, value)| value.into_owned());
+        }
+        request = Request::from_parts(parts, Body::from(bytes));
+    }
+
+    for rule in policy.rules {
+        let value = match rule.key {
+            RateLimitKey::Ip => ip.as_ref(),
+            RateLimitKey::Account => account_id.as_ref(),
+            RateLimitKey::Email => email.as_ref(),
+            RateLimitKey::Client => client_id.as_ref(),
+        };
+        let Some(value) = value else {
+            continue;
+        };
+
+        // Hashed so emails and addresses don't sit in Redis in the clear.
+        let key = format!("ratelimit:{}:{}:{}", policy.name, rule.key.as_str(), hash_token(value));
+
+        match state.rate_limit.check(&key, rule).await {
+            Ok(None) => {}
+            Ok(Some(wait)) => {
+                let retry_after = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+                return base::response
+                    ::error::<u8>(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        "Too many requests, please try again later.",
+                        Some(AppendHeaders(vec![(RETRY_AFTER, retry_after.to_string())]))
+                    )
+                    .into_response();
+            }
+            Err(error) => {
+                tracing::error!("Unable to check rate limit `{}`: {}", policy.name, error);
+            }
+        }
+    }
+
+    next.run(request).await.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn allows_a_full_burst_then_one_per_interval() {
+        // 4 requests a minute, one every 15 seconds once the burst is spent.
+        let (interval, window) = (15 * SECOND, 60 * SECOND);
+        let now = 1_000 * SECOND;
+
+        let mut tat = None;
+        for _ in 0..4 {
+            tat = Some(gcra(tat, now, interval, window).unwrap());
+        }
+        assert_eq!(tat, Some(now + window));
+
+        assert_eq!(gcra(tat, now, interval, window), Err(interval));
+        assert_eq!(gcra(tat, now + 5 * SECOND, interval, window), Err(10 * SECOND));
+        assert_eq!(gcra(tat, now + interval, interval, window), Ok(now + window + interval));
+    }
+
+    #[test]
+    fn idle_keys_start_over() {
+        let (interval, window) = (15 * SECOND, 60 * SECOND);
+        let now = 1_000 * SECOND;
+
+        assert_eq!(gcra(None, now, interval, window), Ok(now + interval));
+        assert_eq!(gcra(Some(now - window), now, interval, window), Ok(now + interval));
+    }
+
+    #[test]
+    fn a_limit_of_one_waits_the_whole_window() {
+        let window = 60 * SECOND;
+        let now = 1_000 * SECOND;
+
+        let tat = gcra(None, now, window, window).unwrap();
+        assert_eq!(gcra(Some(tat), now, window, window), Err(window));
+        assert_eq!(gcra(Some(tat), now + window, window, window), Ok(now + 2 * window));
+    }
+
+    #[tokio::test]
+    async fn memory_store_keeps_keys_apart() {
+        let limiter = RateLimiter::memory();
+        let rule = RateLimitRule { key: RateLimitKey::Ip, limit: 2, window: hours(1) };
+
+        assert_eq!(limiter.check("a", &rule).await.unwrap(), None);
+        assert_eq!(limiter.check("a", &rule).await.unwrap(), None);
+        assert!(limiter.check("a", &rule).await.unwrap().is_some_and(|wait| wait > hours(0)));
+        assert_eq!(limiter.check("b", &rule).await.unwrap(), None);
+    }
+}
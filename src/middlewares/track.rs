@@ -1,7 +1,87 @@
-use axum::{ extract::Request, middleware::Next, response::IntoResponse };
+use std::time::Instant;
+
+use axum::{
+    extract::{ MatchedPath, Request },
+    http::{ HeaderName, HeaderValue },
+    middleware::Next,
+    response::{ IntoResponse, Response },
+};
+use nanoid::nanoid;
+use tracing::{ Instrument, field::Empty };
+
+use crate::{ middlewares::client_ip::ClientIp, utils::{ metrics, redact } };
+
+/// Ties a response to its log lines, taken from the request when the edge already set one.
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
 
 pub async fn log_requests(request: Request, next: Next) -> impl IntoResponse {
-    tracing::info!("{} {}", request.method(), request.uri());
+    tracing::info!("{} {}", request.method(), redact::Query(request.uri()));
 
     next.run(request).await
 }
+
+/// Counts requests and times them, by route template so IDs in paths don't explode the labels.
+pub async fn measure(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => "unmatched".to_string(),
+    };
+
+    let response = next.run(request).await.into_response();
+    metrics::request(method, route, response.status().as_u16(), started);
+
+    response
+}
+
+/// Span around the whole request, named after the route template, with the request ID every
+/// log line in it carries.
+pub async fn trace(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map(String::from)
+        .unwrap_or_else(|| nanoid!());
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(|ClientIp(ip)| redact::Ip(*ip).to_string());
+
+    let method = request.method().to_string();
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => "unmatched".to_string(),
+    };
+
+    let span = tracing::info_span!(
+        "request",
+        otel.name = format!("{} {}", method, route),
+        otel.kind = "server",
+        http.request.method = method,
+        http.route = route,
+        http.response.status_code = Empty,
+        client.address = client_ip,
+        request_id,
+    );
+
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+
+    response
+}
+
+/// Anything else could be used to forge log lines.
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty() &&
+        value.len() <= 64 &&
+        value
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character))
+}
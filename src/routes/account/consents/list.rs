@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use axum::{ Extension, extract::State, http::StatusCode };
+use serde::Serialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::personal_token::ACCOUNT_READ_SCOPE,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+};
+
+#[derive(Serialize)]
+pub struct ConsentEntry {
+    client_id: String,
+    name: Option<String>,
+    scope: Vec<String>,
+    /// What the client said it needed each sensitive scope for.
+    purposes: BTreeMap<String, String>,
+    granted_at: i64,
+    updated_at: i64,
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>
+) -> ResponseModel<Vec<ConsentEntry>> {
+    let Some(account_id) = authorization_info.account_id(ACCOUNT_READ_SCOPE) else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    let consents = match state.app.db.consent.list(account_id).await {
+        Ok(consents) => consents,
+        Err(error) => {
+            tracing::error!("Unable to list consents for {}: {}", account_id, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    let mut entries = Vec::with_capacity(consents.len());
+    for consent in consents {
+        let name = match state.app.db.client.get(&consent.client_id).await {
+            Ok(client) => client.map(|client| client.name),
+            Err(error) => {
+                tracing::error!("Unable to retreive client {}: {}", consent.client_id, error);
+                return base::response::internal_error(None);
+            }
+        };
+
+        entries.push(ConsentEntry {
+            client_id: consent.client_id,
+            name,
+            scope: consent.scope,
+            purposes: consent.purposes,
+            granted_at: consent.granted_at.timestamp_millis(),
+            updated_at: consent.updated_at.timestamp_millis(),
+        });
+    }
+
+    base::response::result(StatusCode::OK, entries, None)
+}
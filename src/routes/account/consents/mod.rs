@@ -0,0 +1,14 @@
+use axum::Router;
+use axum::routing::{ delete, get };
+
+use crate::{ routes::account::AccountRoutesState };
+
+mod list;
+mod revoke;
+
+pub fn routes(state: AccountRoutesState) -> Router<AccountRoutesState> {
+    Router::new()
+        .route("/", get(list::handler))
+        .route("/{client_id}", delete(revoke::handler))
+        .with_state(state)
+}
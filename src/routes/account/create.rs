@@ -9,7 +9,8 @@ use crate::{
     env::{ ACCOUNT_ID_LENGTH, EMAIL_VERIFY_CODE_LENGTH },
     middlewares::auth::AuthorizationInfo,
     routes::account::AccountRoutesState,
-    workers::verify_email::VerifyEmailRequest,
+    utils::redact,
+    workers::email::EmailRequest,
 };
 use nanoid::nanoid;
 
@@ -71,7 +72,7 @@ pub async fn handler(
             return base::response::error(StatusCode::CONFLICT, "Email already registered.", None);
         }
         Err(error) => {
-            tracing::error!("Database failed to find {}: {}", &payload.email, error);
+            tracing::error!("Database failed to find {}: {}", redact::Email(&payload.email), error);
             return base::response::internal_error(None);
         }
     }
@@ -83,11 +84,15 @@ pub async fn handler(
         "debug".to_string()
     };
     let password_hash = match state.app.worker.hash_pass.send(payload.password).await {
-        Ok(hash) => hash,
-        Err(error) => {
+        Ok(Ok(hash)) => hash,
+        Ok(Err(error)) => {
             tracing::error!("Hash password worker failed when creating an account: {error}");
             return base::response::internal_error(None);
         }
+        Err(error) => {
+            tracing::error!("Unable to hash a password when creating an account: {error}");
+            return base::response::internal_error(None);
+        }
     };
 
     let account = AccountDocument {
@@ -107,12 +112,16 @@ pub async fn handler(
             return base::response::error(StatusCode::CONFLICT, "Email already registered.", None);
         }
         Err(error) => {
-            tracing::error!("Database failed to store {}: {}", &payload.email, error);
+            tracing::error!(
+                "Database failed to store {}: {}",
+                redact::Email(&payload.email),
+                error
+            );
             return base::response::internal_error(None);
         }
     }
 
-    state.app.worker.verify_email.send_ignore(VerifyEmailRequest {
+    state.app.worker.email.send_ignore(EmailRequest::Verify {
         email: payload.email,
         verify_code,
     }).await;
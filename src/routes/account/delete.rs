@@ -9,6 +9,7 @@ use crate::{
     base::{ self, cookies, response::ResponseModel },
     middlewares::auth::AuthorizationInfo,
     routes::account::AccountRoutesState,
+    utils::logout,
 };
 
 pub async fn handler(
@@ -37,6 +38,29 @@ pub async fn handler(
         }
     }
 
+    // Nothing issued to clients should outlive the account either.
+    match state.app.db.grant.clone().revoke_all(&token.account_id).await {
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!("Unable to revoke all grants for {}: {}", &token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    match state.app.db.personal_token.revoke_all(&token.account_id).await {
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!(
+                "Unable to revoke personal tokens for {}: {}",
+                &token.account_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    logout::notify_clients(&state.app, &token.account_id).await;
+
     base::response::success(
         StatusCode::OK,
         Some(
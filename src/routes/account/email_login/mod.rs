@@ -0,0 +1,30 @@
+use axum::{ Router, middleware };
+use axum::routing::post;
+
+use crate::{ middlewares::rate_limit, routes::account::AccountRoutesState };
+
+mod request;
+mod redeem;
+
+pub fn routes(state: AccountRoutesState) -> Router<AccountRoutesState> {
+    Router::new()
+        .route(
+            "/",
+            post(request::handler).layer(
+                middleware::from_fn_with_state(
+                    (state.app.clone(), &rate_limit::EMAIL_LOGIN),
+                    rate_limit::limit
+                )
+            )
+        )
+        .route(
+            "/redeem",
+            post(redeem::handler).layer(
+                middleware::from_fn_with_state(
+                    (state.app.clone(), &rate_limit::EMAIL_REDEEM),
+                    rate_limit::limit
+                )
+            )
+        )
+        .with_state(state)
+}
@@ -0,0 +1,118 @@
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use serde::Deserialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::email_code::EmailCodePurpose,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::{ AccountRoutesState, login::{ self, LoginResponse } },
+    utils::{ metrics, oauth::hash_token, oidc::SignIn, redact },
+};
+
+/// Either the `token` from the emailed link, or the `email` with the `code` typed in.
+#[derive(Deserialize)]
+pub struct RedeemPayload {
+    pub token: Option<String>,
+    pub email: Option<String>,
+    pub code: Option<String>,
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Json(payload): Json<RedeemPayload>
+) -> ResponseModel<LoginResponse> {
+    if authorization_info.active {
+        return base::response::error(
+            StatusCode::FORBIDDEN,
+            "There's already an active account.",
+            None
+        );
+    }
+
+    let account_id = match (payload.token, payload.email, payload.code) {
+        (Some(token), _, _) => {
+            let redeemed = state.app.db.email_code.redeem_link(
+                &hash_token(&token),
+                EmailCodePurpose::Login
+            ).await;
+
+            match redeemed {
+                Ok(Some(document)) => document.account_id,
+                Ok(None) => {
+                    metrics::login("email", false);
+                    return base::response::error(
+                        StatusCode::NOT_FOUND,
+                        "This link expired or was already used.",
+                        None
+                    );
+                }
+                Err(error) => {
+                    tracing::error!("Unable to redeem a login link: {}", error);
+                    return base::response::internal_error(None);
+                }
+            }
+        }
+        (None, Some(email), Some(code)) => {
+            let account = match state.app.db.account.get_from_email(&email).await {
+                Ok(Some(account)) => account,
+                Ok(None) => {
+                    return wrong_code();
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to retreive account for {}: {}",
+                        redact::Email(&email),
+                        error
+                    );
+                    return base::response::internal_error(None);
+                }
+            };
+
+            let attempt = state.app.db.email_code.attempt(
+                &account.account_id,
+                EmailCodePurpose::Login,
+                &hash_token(&code)
+            ).await;
+
+            match attempt {
+                Ok(true) => account.account_id,
+                Ok(false) => {
+                    return wrong_code();
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to check login code for {}: {}",
+                        account.account_id,
+                        error
+                    );
+                    return base::response::internal_error(None);
+                }
+            }
+        }
+        _ => {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "Either a token, or an email with a code is required.",
+                None
+            );
+        }
+    };
+
+    match state.app.db.account.get_from_id(&account_id).await {
+        Ok(Some(account)) => {
+            metrics::login("email", true);
+            login::complete(&state, account, SignIn::Email).await
+        }
+        Ok(None) => wrong_code(),
+        Err(error) => {
+            tracing::error!("Unable to retreive account for {}: {}", account_id, error);
+            base::response::internal_error(None)
+        }
+    }
+}
+
+fn wrong_code() -> ResponseModel<LoginResponse> {
+    metrics::login("email", false);
+    base::response::error(StatusCode::NOT_FOUND, "Wrong email or code.", None)
+}
@@ -0,0 +1,119 @@
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::email_code::{ CODE_ALPHABET, EmailCodeDocument, EmailCodePurpose },
+    env::EMAIL_VERIFY_CODE_LENGTH,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+    utils::{ oauth::hash_token, redact },
+    workers::email::EmailRequest,
+};
+
+#[derive(Deserialize, Validate, Clone)]
+pub struct EmailLoginPayload {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(max = 2048))]
+    pub turnstile_token: String,
+}
+
+/// Emails a login link and code.
+///
+/// Answers the same whether or not the email has an account, like `login` does.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Json(payload): Json<EmailLoginPayload>
+) -> ResponseModel {
+    if authorization_info.active {
+        return base::response::error(
+            StatusCode::FORBIDDEN,
+            "There's already an active account.",
+            None
+        );
+    }
+
+    match payload.validate() {
+        Ok(_) => {}
+        Err(field) => {
+            if let Some(field) = field.errors().iter().next() {
+                return base::response::error(
+                    StatusCode::BAD_REQUEST,
+                    &format!("At least one field is not satisfied: {}", field.0),
+                    None
+                );
+            }
+            return base::response::internal_error(None);
+        }
+    }
+
+    match state.app.turnstile.verify(payload.turnstile_token, state.app.debug).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "Something went wrong, try refresh the page and enter information again.",
+                None
+            );
+        }
+        Err(_) => {
+            tracing::error!("Can't contact Turnstile to verify the code when logging in by email.");
+            return base::response::internal_error(None);
+        }
+    }
+
+    let sent = base::response::result(
+        StatusCode::OK,
+        "If this email has an account, a login link is on its way.".into(),
+        None
+    );
+
+    let account = match state.app.db.account.get_from_email(&payload.email).await {
+        Ok(Some(account)) => account,
+        Ok(None) => {
+            return sent;
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to retreive account for {}: {}",
+                redact::Email(&payload.email),
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    };
+
+    // Unverified accounts have to finish signing up through their verify link first.
+    if account.verify_requested.is_some() {
+        return sent;
+    }
+
+    let login_token = nanoid!(*EMAIL_VERIFY_CODE_LENGTH);
+    let login_code = nanoid!(6, &CODE_ALPHABET);
+    let document = EmailCodeDocument {
+        account_id: account.account_id,
+        purpose: EmailCodePurpose::Login,
+        code_hash: hash_token(&login_code),
+        link_hash: Some(hash_token(&login_token)),
+        attempts: 0,
+        issued_at: bson::DateTime::now(),
+    };
+
+    if let Err(error) = state.app.db.email_code.issue(&document).await {
+        tracing::error!("Unable to issue a login code for {}: {}", document.account_id, error);
+        return base::response::internal_error(None);
+    }
+
+    state.app.worker.email.send_ignore(EmailRequest::Login {
+        email: account.email,
+        login_token,
+        login_code,
+    }).await;
+
+    sent
+}
@@ -0,0 +1,234 @@
+use axum::{
+    Extension,
+    Json,
+    extract::{ Path, State },
+    http::{ HeaderMap, StatusCode, header::COOKIE },
+};
+use cookie_rs::CookieJar;
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::Deserialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::{
+        account::{ AccountDocument, AccountMfaStatus },
+        linked_identity::LinkedIdentityDocument,
+    },
+    env::ACCOUNT_ID_LENGTH,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::{
+        AccountRoutesState,
+        external::{ STATE_COOKIE, link },
+        login::{ self, LoginResponse },
+    },
+    utils::{ oidc::SignIn, redact, upstream },
+};
+
+#[derive(Deserialize)]
+pub struct CallbackPayload {
+    pub code: String,
+    pub state: String,
+}
+
+/// Finishes signing in with a provider, making a new account if the identity isn't known yet,
+/// or linking it when the trip was started from `link`.
+///
+/// An identity only ever signs in to the account it was linked to. A matching email is never
+/// enough to merge into an existing account, as that would hand it to whoever controls the email
+/// at the provider.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Path(provider): Path<String>,
+    headers: HeaderMap,
+    Json(payload): Json<CallbackPayload>
+) -> ResponseModel<LoginResponse> {
+    let Some(identity_provider) = state.app.upstream.get(&provider) else {
+        return base::response::error(StatusCode::NOT_FOUND, "Unknown provider.", None);
+    };
+
+    let cookie_state = headers
+        .get(COOKIE)
+        .and_then(|cookies| cookies.to_str().ok())
+        .and_then(|cookies| CookieJar::parse(cookies).ok())
+        .and_then(|jar| jar.get(STATE_COOKIE).map(|cookie| cookie.value().to_string()));
+
+    if cookie_state.as_deref() != Some(payload.state.as_str()) {
+        return base::response::error(
+            StatusCode::BAD_REQUEST,
+            "This sign in was started somewhere else, please try again.",
+            None
+        );
+    }
+
+    let consumed = state.app.db.upstream_state.consume(&payload.state, &provider).await;
+    let upstream_state = match consumed {
+        Ok(Some(upstream_state)) => upstream_state,
+        Ok(None) => {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "This sign in expired, please try again.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to consume upstream state for {}: {}", provider, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    let identity = match
+        identity_provider.identify(
+            &upstream::redirect_uri(&provider),
+            &payload.code,
+            &upstream_state.code_verifier,
+            &upstream_state.nonce
+        ).await
+    {
+        Ok(identity) => identity,
+        Err(error) => {
+            tracing::warn!("Upstream sign in with {} failed: {}", provider, error);
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "The provider didn't let you in, please try again.",
+                None
+            );
+        }
+    };
+
+    if let Some(account_id) = upstream_state.account_id {
+        // Only the session that started linking can finish it.
+        if authorization_info.token.is_none_or(|token| token.account_id != account_id) {
+            return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+        }
+
+        return link::finish(&state, account_id, provider, identity).await;
+    }
+
+    match state.app.db.linked_identity.get(&provider, &identity.subject).await {
+        Ok(Some(linked)) => {
+            match state.app.db.account.get_from_id(&linked.account_id).await {
+                Ok(Some(account)) => {
+                    return login::complete(&state, account, SignIn::External).await;
+                }
+                Ok(None) => {
+                    // The account ran out its deletion window, the identity is free again.
+                    if
+                        let Err(error) = state.app.db.linked_identity.remove(
+                            &linked.account_id,
+                            &provider,
+                            &identity.subject
+                        ).await
+                    {
+                        tracing::error!(
+                            "Unable to remove stale identity for {}: {}",
+                            linked.account_id,
+                            error
+                        );
+                        return base::response::internal_error(None);
+                    }
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to retreive account for {}: {}",
+                        linked.account_id,
+                        error
+                    );
+                    return base::response::internal_error(None);
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(error) => {
+            tracing::error!("Unable to retreive identity from {}: {}", provider, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let Some(email) = identity.email.filter(|_| identity.email_verified) else {
+        return base::response::error(
+            StatusCode::FORBIDDEN,
+            "The provider didn't share a verified email, please verify one there first.",
+            None
+        );
+    };
+
+    match state.app.db.account.get_from_email(&email).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return email_taken();
+        }
+        Err(error) => {
+            tracing::error!("Database failed to find {}: {}", redact::Email(&email), error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let now = bson::DateTime::now();
+    let account = AccountDocument {
+        account_id: nanoid!(*ACCOUNT_ID_LENGTH),
+        email: email.clone(),
+        password_hash: String::new(),
+        mfa_status: AccountMfaStatus { totp: false, passkey: false },
+        // The provider already vouched for the email.
+        verify_requested: None,
+        verify_code: None,
+        issued_at: Some(now),
+        deletion_requested: None,
+    };
+
+    let linked = LinkedIdentityDocument {
+        account_id: account.account_id.clone(),
+        provider: provider.clone(),
+        subject: identity.subject,
+        email: Some(email.clone()),
+        linked_at: now,
+    };
+
+    // Link first, two callbacks racing for the same identity must not both make an account.
+    match state.app.db.linked_identity.link(&linked).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(
+                StatusCode::CONFLICT,
+                "This sign in is already being handled, please try again.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to link identity from {}: {}", provider, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    match state.app.db.account.add(&account).await {
+        Ok(true) => {}
+        Ok(false) => {
+            if
+                let Err(error) = state.app.db.linked_identity.remove(
+                    &linked.account_id,
+                    &provider,
+                    &linked.subject
+                ).await
+            {
+                tracing::error!("Unable to undo link for {}: {}", linked.account_id, error);
+            }
+            return email_taken();
+        }
+        Err(error) => {
+            tracing::error!("Database failed to store {}: {}", redact::Email(&email), error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    login::complete(&state, account, SignIn::External).await
+}
+
+fn email_taken() -> ResponseModel<LoginResponse> {
+    base::response::error(
+        StatusCode::CONFLICT,
+        "An account with this email already exists, log in and link the provider in settings.",
+        None
+    )
+}
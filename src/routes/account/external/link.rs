@@ -0,0 +1,136 @@
+use axum::{ Extension, Json, extract::{ Path, State }, http::StatusCode };
+use mongodb::bson;
+use serde::Deserialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::linked_identity::LinkedIdentityDocument,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::{
+        AccountRoutesState,
+        external::start::{ self, StartResponse },
+        login::LoginResponse,
+    },
+    utils::{ redact, sudo, upstream::UpstreamIdentity },
+};
+
+#[derive(Deserialize)]
+pub struct LinkPayload {
+    pub sudo: String,
+}
+
+/// Starts linking a provider to the signed in account, finished by the same callback as
+/// signing in.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Path(provider): Path<String>,
+    Json(payload): Json<LinkPayload>
+) -> ResponseModel<StartResponse> {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match sudo::authorize(&state.app, &token.account_id, &payload.sudo).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(StatusCode::FORBIDDEN, "Sudo required.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to check sudo for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    match state.app.db.linked_identity.get_for_account(&token.account_id, &provider).await {
+        Ok(None) => {}
+        Ok(Some(_)) => {
+            return base::response::error(
+                StatusCode::CONFLICT,
+                "This provider is already linked, unlink it first.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive identities for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    start::begin(&state, provider, Some(token.account_id)).await
+}
+
+/// Links the identity the provider came back with to `account_id`.
+///
+/// Refused when the identity belongs to another account, or when its email is another
+/// account's: that is for the owner of the other account to sort out, never a merge.
+pub async fn finish(
+    state: &AccountRoutesState,
+    account_id: String,
+    provider: String,
+    identity: UpstreamIdentity
+) -> ResponseModel<LoginResponse> {
+    match state.app.db.linked_identity.get(&provider, &identity.subject).await {
+        Ok(None) => {}
+        Ok(Some(linked)) if linked.account_id == account_id => {
+            return base::response::error(
+                StatusCode::CONFLICT,
+                "This identity is already linked to your account.",
+                None
+            );
+        }
+        Ok(Some(_)) => {
+            return base::response::error(
+                StatusCode::CONFLICT,
+                "This identity signs in to another account.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive identity from {}: {}", provider, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    if identity.email_verified && let Some(email) = &identity.email {
+        match state.app.db.account.get_from_email(email).await {
+            Ok(Some(account)) if account.account_id != account_id => {
+                return base::response::error(
+                    StatusCode::CONFLICT,
+                    "The email of this identity belongs to another account.",
+                    None
+                );
+            }
+            Ok(_) => {}
+            Err(error) => {
+                tracing::error!("Database failed to find {}: {}", redact::Email(email), error);
+                return base::response::internal_error(None);
+            }
+        }
+    }
+
+    let linked = LinkedIdentityDocument {
+        account_id,
+        provider,
+        subject: identity.subject,
+        email: identity.email,
+        linked_at: bson::DateTime::now(),
+    };
+
+    match state.app.db.linked_identity.link(&linked).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(
+                StatusCode::CONFLICT,
+                "This identity signs in to another account.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to link identity for {}: {}", linked.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    base::response::result(StatusCode::OK, LoginResponse { partial_login: None }, None)
+}
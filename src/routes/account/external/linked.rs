@@ -0,0 +1,50 @@
+use axum::{ Extension, extract::State, http::StatusCode };
+use serde::Serialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::linked_identity::LinkedIdentityDocument,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+};
+
+#[derive(Serialize)]
+pub struct LinkedEntry {
+    provider: String,
+    email: Option<String>,
+    linked_at: i64,
+}
+
+impl From<LinkedIdentityDocument> for LinkedEntry {
+    fn from(document: LinkedIdentityDocument) -> Self {
+        LinkedEntry {
+            provider: document.provider,
+            email: document.email,
+            linked_at: document.linked_at.timestamp_millis(),
+        }
+    }
+}
+
+/// Providers linked to the signed in account.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>
+) -> ResponseModel<Vec<LinkedEntry>> {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match state.app.db.linked_identity.list(&token.account_id).await {
+        Ok(documents) => {
+            base::response::result(
+                StatusCode::OK,
+                documents.into_iter().map(LinkedEntry::from).collect(),
+                None
+            )
+        }
+        Err(error) => {
+            tracing::error!("Unable to list identities for {}: {}", token.account_id, error);
+            base::response::internal_error(None)
+        }
+    }
+}
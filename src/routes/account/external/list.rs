@@ -0,0 +1,8 @@
+use axum::{ extract::State, http::StatusCode };
+
+use crate::{ base::{ self, response::ResponseModel }, routes::account::AccountRoutesState };
+
+/// Providers the frontend can offer to sign in with.
+pub async fn handler(State(state): State<AccountRoutesState>) -> ResponseModel<Vec<String>> {
+    base::response::result(StatusCode::OK, state.app.upstream.names(), None)
+}
@@ -0,0 +1,24 @@
+use axum::Router;
+use axum::routing::{ get, post };
+
+use crate::{ routes::account::AccountRoutesState };
+
+mod list;
+mod start;
+mod callback;
+mod linked;
+mod link;
+mod unlink;
+
+/// Cookie holding the `state` of the trip to the provider, ties the callback to this browser.
+const STATE_COOKIE: &str = "upstream_state";
+
+pub fn routes(state: AccountRoutesState) -> Router<AccountRoutesState> {
+    Router::new()
+        .route("/", get(list::handler))
+        .route("/linked", get(linked::handler))
+        .route("/{provider}", post(start::handler).delete(unlink::handler))
+        .route("/{provider}/link", post(link::handler))
+        .route("/{provider}/callback", post(callback::handler))
+        .with_state(state)
+}
@@ -0,0 +1,105 @@
+use axum::{
+    Extension,
+    extract::{ Path, State },
+    http::{ StatusCode, header::SET_COOKIE },
+    response::AppendHeaders,
+};
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::Serialize;
+
+use crate::{
+    base::{ self, cookies, response::ResponseModel },
+    database::upstream_state::UpstreamStateDocument,
+    env::UPSTREAM_STATE_MAX_AGE,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::{ AccountRoutesState, external::STATE_COOKIE },
+    utils::{ oauth::pkce_challenge, upstream },
+};
+
+#[derive(Serialize)]
+pub struct StartResponse {
+    /// Where the frontend should send the user.
+    redirect: String,
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Path(provider): Path<String>
+) -> ResponseModel<StartResponse> {
+    if authorization_info.active {
+        return base::response::error(
+            StatusCode::FORBIDDEN,
+            "There's already an active account.",
+            None
+        );
+    }
+
+    begin(&state, provider, None).await
+}
+
+/// Sends the user off to the provider, to sign in or to link the identity to `account_id`.
+pub async fn begin(
+    state: &AccountRoutesState,
+    provider: String,
+    account_id: Option<String>
+) -> ResponseModel<StartResponse> {
+    let Some(identity_provider) = state.app.upstream.get(&provider) else {
+        return base::response::error(StatusCode::NOT_FOUND, "Unknown provider.", None);
+    };
+
+    let document = UpstreamStateDocument {
+        state: nanoid!(32),
+        provider: provider.clone(),
+        // RFC 7636 wants 43 to 128 characters.
+        code_verifier: nanoid!(64),
+        nonce: nanoid!(32),
+        account_id,
+        issued_at: bson::DateTime::now(),
+    };
+
+    let redirect = match
+        identity_provider.authorization_url(
+            &upstream::redirect_uri(&provider),
+            &document.state,
+            &document.nonce,
+            &pkce_challenge(&document.code_verifier)
+        ).await
+    {
+        Ok(redirect) => redirect,
+        Err(error) => {
+            tracing::error!("Unable to reach upstream provider {}: {}", provider, error);
+            return base::response::error(
+                StatusCode::BAD_GATEWAY,
+                "The provider can't be reached right now, please try again later.",
+                None
+            );
+        }
+    };
+
+    match state.app.db.upstream_state.add(&document).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return base::response::internal_error(None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to store upstream state for {}: {}", provider, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let state_cookie = cookies::construct(
+        STATE_COOKIE,
+        document.state,
+        "/account/external",
+        *UPSTREAM_STATE_MAX_AGE
+    );
+
+    base::response::result(
+        StatusCode::OK,
+        StartResponse { redirect: redirect.to_string() },
+        Some(AppendHeaders(vec![(SET_COOKIE, state_cookie)]))
+    )
+}
@@ -0,0 +1,71 @@
+use axum::{ Extension, Json, extract::{ Path, State }, http::StatusCode };
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::linked_identity::UnlinkOutcome,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::{ AccountRoutesState, external::link::LinkPayload },
+    utils::sudo,
+};
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Path(provider): Path<String>,
+    Json(payload): Json<LinkPayload>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match sudo::authorize(&state.app, &token.account_id, &payload.sudo).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(StatusCode::FORBIDDEN, "Sudo required.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to check sudo for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let account = match state.app.db.account.get_active_from_id(&token.account_id).await {
+        Ok(Some(account)) => account,
+        Ok(None) => {
+            return base::response::error(
+                StatusCode::NOT_FOUND,
+                "The account is currently on hold.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive account for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    // Without a password, the last identity is the only way back in.
+    let unlinked = state.app.db.linked_identity.unlink(
+        &account.account_id,
+        &provider,
+        !account.has_password()
+    ).await;
+
+    match unlinked {
+        Ok(UnlinkOutcome::Unlinked) => base::response::success(StatusCode::OK, None),
+        Ok(UnlinkOutcome::NotLinked) => {
+            base::response::error(StatusCode::NOT_FOUND, "This provider isn't linked.", None)
+        }
+        Ok(UnlinkOutcome::LastIdentity) => {
+            base::response::error(
+                StatusCode::CONFLICT,
+                "This is the only way to sign in to your account, link another provider first.",
+                None
+            )
+        }
+        Err(error) => {
+            tracing::error!("Unable to unlink identity for {}: {}", account.account_id, error);
+            base::response::internal_error(None)
+        }
+    }
+}
@@ -11,11 +11,12 @@ use validator::Validate;
 
 use crate::{
     base::{ self, cookies, response::ResponseModel },
+    database::account::AccountDocument,
     env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, PARTIAL_LOGIN_MAX_AGE, REFRESH_MAX_AGE, TOKEN_MAX_AGE },
     middlewares::auth::AuthorizationInfo,
     routes::account::AccountRoutesState,
-    utils::{ jwt::{ KeyClaims, KeyKind }, timestamp },
-    workers::verify_pass::VerifyPassRequest,
+    utils::{ jwt::{ KeyClaims, KeyKind }, metrics, oidc::SignIn, redact, timestamp },
+    workers::{ email::EmailRequest, verify_pass::VerifyPassRequest },
 };
 
 #[derive(Deserialize, Validate, Clone)]
@@ -79,30 +80,89 @@ pub async fn handler(
     let account = match state.app.db.account.get_from_email(&payload.email).await {
         Ok(Some(account)) => account,
         Ok(None) => {
+            metrics::login("password", false);
             return base::response::error(StatusCode::NOT_FOUND, "Wrong email or password.", None);
         }
         Err(error) => {
-            tracing::error!("Unable to retreive account for {}: {}", payload.email, error);
+            tracing::error!(
+                "Unable to retreive account for {}: {}",
+                redact::Email(&payload.email),
+                error
+            );
             return base::response::internal_error(None);
         }
     };
 
+    // Accounts made through an upstream provider have no password to match.
+    if !account.has_password() {
+        metrics::login("password", false);
+        return base::response::error(StatusCode::NOT_FOUND, "Wrong email or password.", None);
+    }
+
+    // Answers as if the password was wrong, a lock must not tell the account exists.
+    match state.app.db.lockout.clone().is_locked(&account.account_id).await {
+        Ok(false) => {}
+        Ok(true) => {
+            metrics::login("password", false);
+            return base::response::error(StatusCode::NOT_FOUND, "Wrong email or password.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to check lockout for {}: {}", account.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
     let verify_pass_request = VerifyPassRequest {
         password: payload.password,
-        hash: account.password_hash,
+        hash: account.password_hash.clone(),
     };
 
     match state.app.worker.verify_pass.send(verify_pass_request).await {
-        Ok(true) => {}
-        Ok(false) => {
+        Ok(Ok(true)) => {}
+        Ok(Ok(false)) => {
+            match state.app.db.lockout.clone().fail(&account.account_id).await {
+                Ok(failure) if failure.locked => {
+                    state.app.worker.email.send_ignore(EmailRequest::Lockout {
+                        email: account.email,
+                    }).await;
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to count failure for {}: {}",
+                        account.account_id,
+                        error
+                    );
+                }
+            }
+            metrics::login("password", false);
             return base::response::error(StatusCode::NOT_FOUND, "Wrong email or password.", None);
         }
-        Err(error) => {
+        Ok(Err(error)) => {
             tracing::error!("Verify password worker failure for {}: {error}", account.account_id);
             return base::response::internal_error(None);
         }
+        Err(error) => {
+            tracing::error!("Unable to verify the password of {}: {error}", account.account_id);
+            return base::response::internal_error(None);
+        }
     }
 
+    if let Err(error) = state.app.db.lockout.clone().reset(&account.account_id).await {
+        tracing::error!("Unable to reset lockout for {}: {}", account.account_id, error);
+    }
+    metrics::login("password", true);
+
+    complete(&state, account, SignIn::Password).await
+}
+
+/// Everything after the user proved who they are: a partial login if the account has MFA,
+/// a full session otherwise.
+pub async fn complete(
+    state: &AccountRoutesState,
+    account: AccountDocument,
+    method: SignIn
+) -> ResponseModel<LoginResponse> {
     match account.verify_requested {
         None => {}
         Some(_) => {
@@ -137,6 +197,8 @@ pub async fn handler(
                 kind: KeyKind::PartialLogin,
                 iat: issued_at,
                 exp: issued_at + *PARTIAL_LOGIN_MAX_AGE,
+                auth_time: issued_at,
+                amr: method.amr(),
             });
 
             return base::response::result(
@@ -153,6 +215,8 @@ pub async fn handler(
         kind: KeyKind::Authentication,
         iat: issued_at,
         exp: issued_at + *TOKEN_MAX_AGE,
+        auth_time: issued_at,
+        amr: method.amr(),
     });
 
     let signed_refresh = state.app.jwt.generate(KeyClaims {
@@ -161,6 +225,8 @@ pub async fn handler(
         kind: KeyKind::Refresh,
         iat: issued_at,
         exp: issued_at + *REFRESH_MAX_AGE,
+        auth_time: issued_at,
+        amr: method.amr(),
     });
 
     match state.app.db.auth.clone().issue(account.account_id.clone(), identifier, issued_at).await {
@@ -10,6 +10,7 @@ use crate::{
     base::{ self, cookies, response::ResponseModel },
     middlewares::auth::AuthorizationInfo,
     routes::account::AccountRoutesState,
+    utils::logout,
 };
 
 #[derive(Deserialize)]
@@ -57,6 +58,8 @@ pub async fn handler(
         }
     }
 
+    logout::notify_clients(&state.app, &token.account_id).await;
+
     base::response::success(
         StatusCode::OK,
         Some(
@@ -2,15 +2,20 @@ use std::{ sync::Arc, time::Duration };
 
 use axum::{ Router, routing::{ get, patch, post } };
 
-use crate::{ AppState, middlewares::{ auth, time } };
+use crate::{ AppState, middlewares::{ auth, rate_limit, time } };
 
 mod create;
 mod verify;
-mod login;
+pub mod login;
 mod totp;
 mod logout;
 mod delete;
 mod sudo;
+mod services;
+mod consents;
+mod tokens;
+mod external;
+mod email_login;
 pub mod refresh;
 
 #[derive(Clone)]
@@ -24,12 +29,35 @@ pub fn routes(app_state: Arc<AppState>) -> Router {
     };
 
     Router::new()
-        .route("/", post(create::handler).delete(delete::handler))
+        .route(
+            "/",
+            post(create::handler)
+                .layer(
+                    axum::middleware::from_fn_with_state(
+                        (state.app.clone(), &rate_limit::SIGNUP),
+                        rate_limit::limit
+                    )
+                )
+                .delete(delete::handler)
+        )
         .route("/verify", patch(verify::handler))
-        .route("/login", post(login::handler))
-        .route("/refresh", get(refresh::handler))
+        .route(
+            "/login",
+            post(login::handler).layer(
+                axum::middleware::from_fn_with_state(
+                    (state.app.clone(), &rate_limit::LOGIN),
+                    rate_limit::limit
+                )
+            )
+        )
+        .nest("/login/email", email_login::routes(state.clone()))
+        .route("/refresh", get(refresh::handler).post(refresh::body_handler))
         .nest("/sudo", sudo::routes(state.clone()))
         .nest("/totp", totp::routes(state.clone()))
+        .nest("/services", services::routes(state.clone()))
+        .nest("/consents", consents::routes(state.clone()))
+        .nest("/tokens", tokens::routes(state.clone()))
+        .nest("/external", external::routes(state.clone()))
         .route("/logout", get(logout::handler))
         .layer(axum::middleware::from_fn_with_state(state.app.clone(), auth::authorize))
         .layer(axum::middleware::from_fn_with_state(Duration::from_millis(800), time::padding))
@@ -1,6 +1,7 @@
-use axum::{ Extension, extract::State, response::AppendHeaders };
+use axum::{ Extension, Json, extract::State, response::AppendHeaders };
 use nanoid::nanoid;
 use reqwest::{ StatusCode, header::SET_COOKIE };
+use serde::{ Deserialize, Serialize };
 
 use crate::{
     base::{ self, cookies, response::ResponseModel },
@@ -10,6 +11,17 @@ use crate::{
     utils::{ jwt::{ KeyClaims, KeyKind }, timestamp },
 };
 
+#[derive(Deserialize)]
+pub struct RefreshPayload {
+    pub refresh: String,
+}
+
+#[derive(Serialize)]
+pub struct RefreshResponse {
+    pub token: String,
+    pub refresh: String,
+}
+
 pub async fn handler(
     Extension(authorization_info): Extension<AuthorizationInfo>,
     State(state): State<AccountRoutesState>
@@ -18,6 +30,63 @@ pub async fn handler(
         return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
     };
 
+    let (signed_token, signed_refresh) = match rotate(&state, revoking_refresh).await {
+        Ok(pair) => pair,
+        Err(response) => {
+            return response;
+        }
+    };
+
+    let token_cookie = cookies::construct("token", signed_token, "/", *TOKEN_MAX_AGE);
+    let refresh_cookie = cookies::construct(
+        "refresh",
+        signed_refresh,
+        "/account/refresh",
+        *REFRESH_MAX_AGE
+    );
+
+    base::response::success(
+        StatusCode::OK,
+        Some(AppendHeaders(vec![(SET_COOKIE, token_cookie), (SET_COOKIE, refresh_cookie)]))
+    )
+}
+
+/// For clients holding their tokens themselves, the pair goes both ways in the body.
+pub async fn body_handler(
+    State(state): State<AccountRoutesState>,
+    Json(payload): Json<RefreshPayload>
+) -> ResponseModel<RefreshResponse> {
+    let Some(revoking_refresh) = state.app.jwt.verify(&payload.refresh, KeyKind::Refresh) else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match state.app.db.auth.clone().check_token(&revoking_refresh).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+        }
+        Err(error) => {
+            tracing::error!(
+                "Failed to query database for refresh `{}`: {error}",
+                revoking_refresh.identifier
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    match rotate(&state, revoking_refresh).await {
+        Ok((token, refresh)) => {
+            base::response::result(StatusCode::OK, RefreshResponse { token, refresh }, None)
+        }
+        Err(response) => response,
+    }
+}
+
+/// Trades a checked refresh token for a new pair, the old pair stops working.
+async fn rotate<R>(
+    state: &AccountRoutesState,
+    revoking_refresh: KeyClaims
+) -> Result<(String, String), ResponseModel<R>> {
     let issued_at = timestamp::now();
     let identifier = nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH);
 
@@ -27,6 +96,8 @@ pub async fn handler(
         kind: KeyKind::Authentication,
         iat: issued_at,
         exp: issued_at + *TOKEN_MAX_AGE,
+        auth_time: revoking_refresh.auth_time,
+        amr: revoking_refresh.amr.clone(),
     });
 
     let signed_refresh = state.app.jwt.generate(KeyClaims {
@@ -35,6 +106,8 @@ pub async fn handler(
         kind: KeyKind::Refresh,
         iat: issued_at,
         exp: issued_at + *REFRESH_MAX_AGE,
+        auth_time: revoking_refresh.auth_time,
+        amr: revoking_refresh.amr.clone(),
     });
 
     match
@@ -45,10 +118,12 @@ pub async fn handler(
         Ok(true) => {}
         Ok(false) => {
             tracing::error!("A nanoid collision was found.");
-            return base::response::error(
-                StatusCode::CONFLICT,
-                "Thank you for being this rare.",
-                None
+            return Err(
+                base::response::error(
+                    StatusCode::CONFLICT,
+                    "Thank you for being this rare.",
+                    None
+                )
             );
         }
         Err(_) => {
@@ -56,34 +131,25 @@ pub async fn handler(
                 "Can't push a new token into database for {}",
                 revoking_refresh.account_id
             );
-            return base::response::internal_error(None);
+            return Err(base::response::internal_error(None));
         }
     }
 
     match state.app.db.auth.clone().revoke(&revoking_refresh).await {
         Ok(true) => {}
         Ok(false) => {
-            return base::response::error(
-                StatusCode::FORBIDDEN,
-                "There is an exisiting TOTP. Please delete it first.",
-                None
+            return Err(
+                base::response::error(
+                    StatusCode::FORBIDDEN,
+                    "There is an exisiting TOTP. Please delete it first.",
+                    None
+                )
             );
         }
         Err(_) => {
-            return base::response::internal_error(None);
+            return Err(base::response::internal_error(None));
         }
     }
 
-    let token_cookie = cookies::construct("token", signed_token, "/", *TOKEN_MAX_AGE);
-    let refresh_cookie = cookies::construct(
-        "refresh",
-        signed_refresh,
-        "/account/refresh",
-        *REFRESH_MAX_AGE
-    );
-
-    base::response::success(
-        StatusCode::OK,
-        Some(AppendHeaders(vec![(SET_COOKIE, token_cookie), (SET_COOKIE, refresh_cookie)]))
-    )
+    Ok((signed_token, signed_refresh))
 }
@@ -0,0 +1,56 @@
+use axum::{ Extension, extract::State, http::StatusCode };
+use serde::Serialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::personal_token::ACCOUNT_READ_SCOPE,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+};
+
+#[derive(Serialize)]
+pub struct ServiceEntry {
+    client_id: String,
+    name: Option<String>,
+    /// The identifier this service knows the account by.
+    subject: String,
+    issued_at: i64,
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>
+) -> ResponseModel<Vec<ServiceEntry>> {
+    let Some(account_id) = authorization_info.account_id(ACCOUNT_READ_SCOPE) else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    let subjects = match state.app.db.pairwise.list(account_id).await {
+        Ok(subjects) => subjects,
+        Err(error) => {
+            tracing::error!("Unable to list services for {}: {}", account_id, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    let mut services = Vec::with_capacity(subjects.len());
+    for subject in subjects {
+        // A client removed from the registry still shows up, the user may want to revoke it.
+        let name = match state.app.db.client.get(&subject.client_id).await {
+            Ok(client) => client.map(|client| client.name),
+            Err(error) => {
+                tracing::error!("Unable to retreive client {}: {}", subject.client_id, error);
+                return base::response::internal_error(None);
+            }
+        };
+
+        services.push(ServiceEntry {
+            client_id: subject.client_id,
+            name,
+            subject: subject.subject,
+            issued_at: subject.issued_at.timestamp_millis(),
+        });
+    }
+
+    base::response::result(StatusCode::OK, services, None)
+}
@@ -0,0 +1,65 @@
+use axum::{ Extension, extract::{ Path, State }, http::StatusCode };
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+};
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Path(client_id): Path<String>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match state.app.db.pairwise.remove(&token.account_id, &client_id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(
+                StatusCode::NOT_FOUND,
+                "This service doesn't hold an identifier for your account.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to remove subject of {} for client {}: {}",
+                token.account_id,
+                client_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    match state.app.db.consent.revoke(&token.account_id, &client_id).await {
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!(
+                "Unable to revoke consent of {} for client {}: {}",
+                token.account_id,
+                client_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    match state.app.db.grant.clone().revoke_client(&token.account_id, &client_id).await {
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!(
+                "Unable to revoke grants of {} for client {}: {}",
+                token.account_id,
+                client_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    base::response::success(StatusCode::OK, None)
+}
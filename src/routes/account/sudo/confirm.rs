@@ -0,0 +1,68 @@
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use serde::Deserialize;
+use validator::Validate;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::email_code::EmailCodePurpose,
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+    utils::{ oauth::hash_token, sudo },
+};
+
+#[derive(Deserialize, Validate, Clone)]
+pub struct ConfirmPayload {
+    #[validate(length(equal = 6))]
+    pub code: String,
+}
+
+/// Trades the emailed code for a `Sudo` token.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Json(payload): Json<ConfirmPayload>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match payload.validate() {
+        Ok(_) => {}
+        Err(_) => {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "The code must be 6 characters.",
+                None
+            );
+        }
+    }
+
+    let attempt = state.app.db.email_code.attempt(
+        &token.account_id,
+        EmailCodePurpose::Sudo,
+        &hash_token(&payload.code)
+    ).await;
+
+    match attempt {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(StatusCode::FORBIDDEN, "Wrong code.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to check sudo code for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    match sudo::grant(&state.app, &token.account_id).await {
+        Ok(Some(signed_sudo)) => base::response::result(StatusCode::OK, signed_sudo.into(), None),
+        Ok(None) => {
+            tracing::error!("A nanoid collision was found.");
+            base::response::internal_error(None)
+        }
+        Err(error) => {
+            tracing::error!("Unable to grant sudo for {}: {}", token.account_id, error);
+            base::response::internal_error(None)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use serde::Deserialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+    utils::sudo,
+};
+
+#[derive(Deserialize)]
+pub struct ElevatePayload {
+    /// Proves the user just passed their second factor, see `/account/totp/authorize`.
+    pub mfa_upgrade: String,
+}
+
+/// Trades an `MfaUpgrade` token for a `Sudo` token, the factor for accounts with MFA.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Json(payload): Json<ElevatePayload>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match sudo::redeem_mfa_upgrade(&state.app, &token.account_id, &payload.mfa_upgrade).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to spend MFA upgrade for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    match sudo::grant(&state.app, &token.account_id).await {
+        Ok(Some(signed_sudo)) => base::response::result(StatusCode::OK, signed_sudo.into(), None),
+        Ok(None) => {
+            tracing::error!("A nanoid collision was found.");
+            base::response::internal_error(None)
+        }
+        Err(error) => {
+            tracing::error!("Unable to grant sudo for {}: {}", token.account_id, error);
+            base::response::internal_error(None)
+        }
+    }
+}
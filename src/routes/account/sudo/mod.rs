@@ -3,16 +3,35 @@ use std::time::Duration;
 use axum::{ Router, middleware };
 use axum::routing::{ get, post };
 
-use crate::middlewares::time;
+use crate::middlewares::{ rate_limit, time };
 use crate::{ routes::account::AccountRoutesState };
 
 mod methods;
 mod request;
 mod elevate;
+mod confirm;
 
 pub fn routes(state: AccountRoutesState) -> Router<AccountRoutesState> {
     Router::new()
         .route("/methods", get(methods::handler))
+        .route(
+            "/request",
+            post(request::handler).layer(
+                middleware::from_fn_with_state(
+                    (state.app.clone(), &rate_limit::SUDO_EMAIL),
+                    rate_limit::limit
+                )
+            )
+        )
+        .route(
+            "/request/confirm",
+            post(confirm::handler).layer(
+                middleware::from_fn_with_state(
+                    (state.app.clone(), &rate_limit::SUDO_CONFIRM),
+                    rate_limit::limit
+                )
+            )
+        )
         .route("/elevate", post(elevate::handler))
         .layer(middleware::from_fn_with_state(Duration::from_secs(2), time::padding))
         .with_state(state)
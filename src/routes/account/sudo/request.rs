@@ -0,0 +1,68 @@
+use axum::{ Extension, extract::State, http::StatusCode };
+use mongodb::bson;
+use nanoid::nanoid;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::email_code::{ CODE_ALPHABET, EmailCodeDocument, EmailCodePurpose },
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+    utils::oauth::hash_token,
+    workers::email::EmailRequest,
+};
+
+/// Emails a sudo code, only for accounts without MFA as `methods` tells.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    let account = match state.app.db.account.get_active_from_id(&token.account_id).await {
+        Ok(Some(account)) => account,
+        Ok(None) => {
+            return base::response::error(
+                StatusCode::NOT_FOUND,
+                "The account is currently on hold.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive account for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    // An email code would be a way around the stronger factor.
+    if account.mfa_status.has_mfa() {
+        return base::response::error(
+            StatusCode::FORBIDDEN,
+            "This account has MFA, please use it instead.",
+            None
+        );
+    }
+
+    let sudo_code = nanoid!(6, &CODE_ALPHABET);
+    let document = EmailCodeDocument {
+        account_id: account.account_id,
+        purpose: EmailCodePurpose::Sudo,
+        code_hash: hash_token(&sudo_code),
+        link_hash: None,
+        attempts: 0,
+        issued_at: bson::DateTime::now(),
+    };
+
+    if let Err(error) = state.app.db.email_code.issue(&document).await {
+        tracing::error!("Unable to issue a sudo code for {}: {}", document.account_id, error);
+        return base::response::internal_error(None);
+    }
+
+    state.app.worker.email.send_ignore(EmailRequest::Sudo {
+        email: account.email,
+        sudo_code,
+    }).await;
+
+    base::response::result(StatusCode::OK, "Check your inbox for the code.".into(), None)
+}
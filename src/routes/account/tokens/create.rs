@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::personal_token::{ ACCOUNT_READ_SCOPE, PersonalTokenDocument, TOKEN_PREFIX },
+    env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, PERSONAL_TOKEN_LENGTH, PERSONAL_TOKEN_SCOPES },
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+    utils::{ oauth::hash_token, sudo },
+};
+
+/// Tokens an account can hold at once.
+const MAX_TOKENS: u64 = 50;
+
+/// The furthest a token can be set to expire, a year.
+const MAX_EXPIRES_IN: Duration = Duration::from_secs(366 * 24 * 60 * 60);
+
+#[derive(Deserialize)]
+pub struct CreatePayload {
+    pub name: String,
+    pub scopes: Vec<String>,
+    /// Seconds until the token stops working, never if left out.
+    pub expires_in: Option<u64>,
+    /// A token outlives the session, so minting one needs the user to prove it's them again.
+    pub sudo: String,
+}
+
+#[derive(Serialize)]
+pub struct CreatedToken {
+    token_id: String,
+    /// Only ever shown here.
+    token: String,
+    name: String,
+    scopes: Vec<String>,
+    expires_at: Option<i64>,
+    issued_at: i64,
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Json(payload): Json<CreatePayload>
+) -> ResponseModel<CreatedToken> {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match sudo::authorize(&state.app, &token.account_id, &payload.sudo).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(StatusCode::FORBIDDEN, "Sudo required.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to check sudo for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let name = payload.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 64 {
+        return base::response::error(
+            StatusCode::BAD_REQUEST,
+            "The name for the token must be 1 to 64 characters.",
+            None
+        );
+    }
+
+    let mut scopes: Vec<String> = Vec::new();
+    for scope in payload.scopes {
+        if !is_known_scope(&scope) {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "A scope is not available to personal access tokens.",
+                None
+            );
+        }
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    if scopes.is_empty() || scopes.len() > 32 {
+        return base::response::error(
+            StatusCode::BAD_REQUEST,
+            "A token needs 1 to 32 scopes.",
+            None
+        );
+    }
+
+    let expires_in = payload.expires_in.map(Duration::from_secs);
+    if expires_in.is_some_and(|expires_in| expires_in.is_zero() || expires_in > MAX_EXPIRES_IN) {
+        return base::response::error(
+            StatusCode::BAD_REQUEST,
+            "A token can expire a year from now at most.",
+            None
+        );
+    }
+
+    match state.app.db.personal_token.count(&token.account_id).await {
+        Ok(count) if count >= MAX_TOKENS => {
+            return base::response::error(
+                StatusCode::FORBIDDEN,
+                "Too many tokens. Please revoke one first.",
+                None
+            );
+        }
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!("Unable to count personal tokens for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let issued_at = bson::DateTime::now();
+    let secret = format!("{}{}", TOKEN_PREFIX, nanoid!(*PERSONAL_TOKEN_LENGTH));
+    let document = PersonalTokenDocument {
+        token_id: nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH),
+        account_id: token.account_id,
+        name,
+        scopes,
+        token_hash: hash_token(&secret),
+        expires_at: expires_in.map(|expires_in| {
+            bson::DateTime::from_millis(
+                issued_at.timestamp_millis() + (expires_in.as_millis() as i64)
+            )
+        }),
+        last_used_at: None,
+        issued_at,
+    };
+
+    match state.app.db.personal_token.add(&document).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return base::response::internal_error(None);
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to create a personal token for {}: {}",
+                document.account_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    base::response::result(
+        StatusCode::CREATED,
+        CreatedToken {
+            token_id: document.token_id,
+            token: secret,
+            name: document.name,
+            scopes: document.scopes,
+            expires_at: document.expires_at.map(|expires_at| expires_at.timestamp_millis()),
+            issued_at: document.issued_at.timestamp_millis(),
+        },
+        None
+    )
+}
+
+/// A scope nothing checks would only give the user a false sense of what the token can do.
+fn is_known_scope(scope: &str) -> bool {
+    scope == ACCOUNT_READ_SCOPE || PERSONAL_TOKEN_SCOPES.iter().any(|known| known == scope)
+}
@@ -0,0 +1,55 @@
+use axum::{ Extension, extract::State, http::StatusCode };
+use serde::Serialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::personal_token::{ ACCOUNT_READ_SCOPE, PersonalTokenDocument },
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+};
+
+#[derive(Serialize)]
+pub struct TokenEntry {
+    token_id: String,
+    name: String,
+    scopes: Vec<String>,
+    expires_at: Option<i64>,
+    last_used_at: Option<i64>,
+    issued_at: i64,
+}
+
+impl From<PersonalTokenDocument> for TokenEntry {
+    fn from(document: PersonalTokenDocument) -> Self {
+        TokenEntry {
+            token_id: document.token_id,
+            name: document.name,
+            scopes: document.scopes,
+            expires_at: document.expires_at.map(|expires_at| expires_at.timestamp_millis()),
+            last_used_at: document.last_used_at.map(|used_at| used_at.timestamp_millis()),
+            issued_at: document.issued_at.timestamp_millis(),
+        }
+    }
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>
+) -> ResponseModel<Vec<TokenEntry>> {
+    let Some(account_id) = authorization_info.account_id(ACCOUNT_READ_SCOPE) else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match state.app.db.personal_token.list(account_id).await {
+        Ok(documents) => {
+            base::response::result(
+                StatusCode::OK,
+                documents.into_iter().map(TokenEntry::from).collect(),
+                None
+            )
+        }
+        Err(error) => {
+            tracing::error!("Unable to list personal tokens for {}: {}", account_id, error);
+            base::response::internal_error(None)
+        }
+    }
+}
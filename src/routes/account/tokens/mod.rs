@@ -0,0 +1,15 @@
+use axum::Router;
+use axum::routing::{ delete, get };
+
+use crate::{ routes::account::AccountRoutesState };
+
+mod list;
+mod create;
+mod revoke;
+
+pub fn routes(state: AccountRoutesState) -> Router<AccountRoutesState> {
+    Router::new()
+        .route("/", get(list::handler).post(create::handler))
+        .route("/{token_id}", delete(revoke::handler))
+        .with_state(state)
+}
@@ -0,0 +1,33 @@
+use axum::{ Extension, extract::{ Path, State }, http::StatusCode };
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    middlewares::auth::AuthorizationInfo,
+    routes::account::AccountRoutesState,
+};
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<AccountRoutesState>,
+    Path(token_id): Path<String>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    match state.app.db.personal_token.revoke(&token.account_id, &token_id).await {
+        Ok(true) => base::response::success(StatusCode::OK, None),
+        Ok(false) => {
+            base::response::error(StatusCode::NOT_FOUND, "There is no such token.", None)
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to revoke personal token {} for {}: {}",
+                token_id,
+                token.account_id,
+                error
+            );
+            base::response::internal_error(None)
+        }
+    }
+}
@@ -124,6 +124,8 @@ pub async fn handler(
         kind: KeyKind::MfaUpgrade,
         iat: issued_at,
         exp: issued_at + *MFA_UPGRADE_MAX_AGE,
+        auth_time: issued_at,
+        amr: vec!["otp".to_string()],
     });
 
     base::response::result(StatusCode::OK, signed_mfa_upgrade.into(), None)
@@ -0,0 +1,8 @@
+use axum::http::StatusCode;
+
+use crate::base::{ self, response::ResponseModel };
+
+/// The process is up and serving, nothing else is checked.
+pub async fn handler() -> ResponseModel {
+    base::response::result(StatusCode::OK, "Alive.".into(), None)
+}
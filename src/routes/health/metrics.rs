@@ -0,0 +1,14 @@
+use axum::{ extract::State, http::header::CONTENT_TYPE, response::IntoResponse };
+
+use crate::{ routes::health::HealthRoutesState, utils::metrics };
+
+/// Prometheus scrape endpoint, worker queues are sampled on every scrape.
+pub async fn handler(State(state): State<HealthRoutesState>) -> impl IntoResponse {
+    let worker = &state.app.worker;
+    metrics::queue_depth("hash_pass", worker.hash_pass.queued());
+    metrics::queue_depth("verify_pass", worker.verify_pass.queued());
+    metrics::queue_depth("email", worker.email.queued());
+    metrics::queue_depth("backchannel_logout", worker.backchannel_logout.queued());
+
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], metrics::render())
+}
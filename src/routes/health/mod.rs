@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use axum::{ Router, routing::get };
+
+use crate::AppState;
+
+mod healthz;
+mod readyz;
+mod metrics;
+
+#[derive(Clone)]
+pub struct HealthRoutesState {
+    pub app: Arc<AppState>,
+}
+
+/// Liveness on the public router, it tells nothing about the internals.
+pub fn routes() -> Router {
+    Router::new().route("/healthz", get(healthz::handler))
+}
+
+/// Unauthenticated probes for orchestrators and monitoring, only served on `OPS_HOST`.
+pub fn ops_routes(app_state: Arc<AppState>) -> Router {
+    let state = HealthRoutesState {
+        app: app_state,
+    };
+
+    Router::new()
+        .route("/healthz", get(healthz::handler))
+        .route("/readyz", get(readyz::handler))
+        .route("/metrics", get(metrics::handler))
+        .with_state(state)
+}
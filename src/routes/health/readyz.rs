@@ -0,0 +1,106 @@
+use std::{ collections::BTreeMap, time::Duration };
+
+use axum::{ extract::State, http::StatusCode };
+use serde::Serialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    routes::health::HealthRoutesState,
+    workers::RequestHandler,
+};
+
+/// Longer than this and the dependency counts as down, the probe itself mustn't hang.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+pub struct ReadyResponse {
+    ready: bool,
+    mongo: ComponentStatus,
+    redis: ComponentStatus,
+    jwt: ComponentStatus,
+    workers: BTreeMap<&'static str, WorkerStatus>,
+}
+
+/// Errors are only logged, they can tell too much about the infrastructure.
+#[derive(Serialize)]
+pub struct ComponentStatus {
+    ready: bool,
+}
+
+#[derive(Serialize)]
+pub struct WorkerStatus {
+    ready: bool,
+    queued: usize,
+    capacity: usize,
+}
+
+impl<R, P> From<&RequestHandler<R, P>> for WorkerStatus {
+    /// A full queue makes every request wait on the workers.
+    fn from(handler: &RequestHandler<R, P>) -> Self {
+        WorkerStatus {
+            ready: handler.queued() < handler.capacity(),
+            queued: handler.queued(),
+            capacity: handler.capacity(),
+        }
+    }
+}
+
+/// Ready when every dependency answers, `503` with what's down otherwise.
+pub async fn handler(State(state): State<HealthRoutesState>) -> ResponseModel<ReadyResponse> {
+    let (mongo, redis) = tokio::join!(
+        tokio::time::timeout(CHECK_TIMEOUT, state.app.db.ping()),
+        tokio::time::timeout(CHECK_TIMEOUT, state.app.db.ping_cache())
+    );
+
+    let mongo = match mongo {
+        Ok(Ok(())) => true,
+        Ok(Err(error)) => {
+            tracing::warn!("Readiness: MongoDB ping failed: {}", error);
+            false
+        }
+        Err(_) => {
+            tracing::warn!("Readiness: MongoDB ping timed out.");
+            false
+        }
+    };
+
+    let redis = match redis {
+        Ok(Ok(())) => true,
+        Ok(Err(error)) => {
+            tracing::warn!("Readiness: Redis ping failed: {}", error);
+            false
+        }
+        Err(_) => {
+            tracing::warn!("Readiness: Redis ping timed out.");
+            false
+        }
+    };
+
+    let worker = &state.app.worker;
+    let workers = BTreeMap::from([
+        ("hash_pass", WorkerStatus::from(&worker.hash_pass)),
+        ("verify_pass", WorkerStatus::from(&worker.verify_pass)),
+        ("email", WorkerStatus::from(&worker.email)),
+        ("backchannel_logout", WorkerStatus::from(&worker.backchannel_logout)),
+    ]);
+
+    // Without the private key no token can be signed, so no one can log in.
+    let jwt = state.app.jwt.can_sign();
+
+    let ready = mongo && redis && jwt && workers.values().all(|worker| worker.ready);
+
+    base::response::result(
+        match ready {
+            true => StatusCode::OK,
+            false => StatusCode::SERVICE_UNAVAILABLE,
+        },
+        ReadyResponse {
+            ready,
+            mongo: ComponentStatus { ready: mongo },
+            redis: ComponentStatus { ready: redis },
+            jwt: ComponentStatus { ready: jwt },
+            workers,
+        },
+        None
+    )
+}
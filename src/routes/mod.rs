@@ -1,2 +1,5 @@
 pub mod account;
+pub mod health;
 pub mod ily;
+pub mod oauth;
+pub mod well_known;
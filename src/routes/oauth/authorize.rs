@@ -0,0 +1,212 @@
+use std::collections::BTreeMap;
+
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::{ Deserialize, Serialize };
+use url::Url;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::{ authorization_code::AuthorizationCodeDocument, pairwise::PairwiseDocument },
+    env::OAUTH_CODE_LENGTH,
+    middlewares::auth::AuthorizationInfo,
+    routes::oauth::OAuthRoutesState,
+    utils::{ oauth::{ SENSITIVE_SCOPES, parse_scope }, oidc, pairwise, sudo },
+};
+
+#[derive(Deserialize)]
+pub struct AuthorizePayload {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String,
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+    pub nonce: Option<String>,
+    /// Proves the user just passed a second factor, reported to the client through `acr`/`amr`.
+    pub mfa_upgrade: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct AuthorizeResponse {
+    /// Where the frontend should send the user next, carrying either the code or the error.
+    pub redirect: String,
+}
+
+/// Called by the frontend once the user approved the client on the consent screen.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<OAuthRoutesState>,
+    Json(payload): Json<AuthorizePayload>
+) -> ResponseModel<AuthorizeResponse> {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    let (auth_time, amr) = match payload.mfa_upgrade {
+        Some(mfa_upgrade) => {
+            let redeemed = sudo::redeem_mfa_upgrade(&state.app, &token.account_id, &mfa_upgrade);
+            let mfa_upgrade = match redeemed.await {
+                Ok(Some(mfa_upgrade)) => mfa_upgrade,
+                Ok(None) => {
+                    return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to spend MFA upgrade for {}: {}",
+                        token.account_id,
+                        error
+                    );
+                    return base::response::internal_error(None);
+                }
+            };
+            (mfa_upgrade.iat, oidc::amr(&token.amr, true))
+        }
+        None => (token.auth_time, oidc::amr(&token.amr, false)),
+    };
+
+    let client = match state.app.db.client.get(&payload.client_id).await {
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return base::response::error(StatusCode::BAD_REQUEST, "Unknown client.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive client {}: {}", payload.client_id, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    // Never redirect to an unregistered address, not even to report an error.
+    if !client.redirect_uris.contains(&payload.redirect_uri) {
+        return base::response::error(
+            StatusCode::BAD_REQUEST,
+            "Redirect URI is not registered for this client.",
+            None
+        );
+    }
+    let Ok(mut redirect) = Url::parse(&payload.redirect_uri) else {
+        return base::response::internal_error(None);
+    };
+
+    if payload.response_type != "code" {
+        return redirect_error(redirect, "unsupported_response_type", payload.state);
+    }
+
+    let scope = parse_scope(&payload.scope);
+    if scope.is_empty() || scope.iter().any(|scope| !client.scopes.contains(scope)) {
+        return redirect_error(redirect, "invalid_scope", payload.state);
+    }
+
+    // The user was shown these reasons on the consent screen, keep a copy of what they agreed to.
+    let mut purposes = BTreeMap::new();
+    for scope in scope.iter().filter(|scope| SENSITIVE_SCOPES.contains(&scope.as_str())) {
+        let Some(purpose) = client.purpose(scope) else {
+            return redirect_error(redirect, "invalid_scope", payload.state);
+        };
+        purposes.insert(scope.clone(), purpose.to_string());
+    }
+
+    match (&payload.code_challenge, payload.code_challenge_method.as_deref()) {
+        (Some(_), Some("S256")) => {}
+        (None, None) if client.is_confidential() => {}
+        _ => {
+            return redirect_error(redirect, "invalid_request", payload.state);
+        }
+    }
+
+    let pairwise_document = PairwiseDocument {
+        account_id: token.account_id.clone(),
+        client_id: client.client_id.clone(),
+        subject: pairwise::subject(&token.account_id, &client.sector_identifier),
+        issued_at: bson::DateTime::now(),
+    };
+
+    match state.app.db.pairwise.record(&pairwise_document).await {
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!(
+                "Unable to record subject of {} for client {}: {}",
+                token.account_id,
+                client.client_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    match
+        state.app.db.consent.grant(&token.account_id, &client.client_id, &scope, &purposes).await
+    {
+        Ok(_) => {}
+        Err(error) => {
+            tracing::error!(
+                "Unable to record consent of {} for client {}: {}",
+                token.account_id,
+                client.client_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    let code_document = AuthorizationCodeDocument {
+        code: nanoid!(*OAUTH_CODE_LENGTH),
+        account_id: token.account_id,
+        client_id: client.client_id,
+        redirect_uri: payload.redirect_uri,
+        scope,
+        code_challenge: payload.code_challenge,
+        nonce: payload.nonce,
+        auth_time: bson::DateTime::from_millis(auth_time.as_millis() as i64),
+        amr,
+        issued_at: bson::DateTime::now(),
+    };
+
+    match state.app.db.authorization_code.add(&code_document).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return base::response::error(
+                StatusCode::CONFLICT,
+                "Thank you for being this rare.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to store authorization code for {}: {}",
+                code_document.account_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    }
+
+    {
+        let mut query = redirect.query_pairs_mut();
+        query.append_pair("code", &code_document.code);
+        if let Some(state) = &payload.state {
+            query.append_pair("state", state);
+        }
+    }
+
+    base::response::result(StatusCode::OK, AuthorizeResponse { redirect: redirect.into() }, None)
+}
+
+fn redirect_error(
+    mut redirect: Url,
+    error: &str,
+    state: Option<String>
+) -> ResponseModel<AuthorizeResponse> {
+    {
+        let mut query = redirect.query_pairs_mut();
+        query.append_pair("error", error);
+        if let Some(state) = &state {
+            query.append_pair("state", state);
+        }
+    }
+
+    base::response::result(StatusCode::OK, AuthorizeResponse { redirect: redirect.into() }, None)
+}
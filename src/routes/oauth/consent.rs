@@ -0,0 +1,106 @@
+use axum::{ Extension, extract::{ Query, State }, http::StatusCode };
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    middlewares::auth::AuthorizationInfo,
+    routes::oauth::OAuthRoutesState,
+    utils::oauth::{ SENSITIVE_SCOPES, parse_scope },
+};
+
+#[derive(Deserialize)]
+pub struct ConsentOptions {
+    pub client_id: String,
+    pub scope: String,
+}
+
+#[derive(Serialize)]
+pub struct ConsentScope {
+    scope: String,
+    /// The client's declared reason, always present for sensitive scopes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purpose: Option<String>,
+    sensitive: bool,
+    /// The user already agreed to this scope for this client.
+    granted: bool,
+}
+
+#[derive(Serialize)]
+pub struct ConsentResponse {
+    client_id: String,
+    name: String,
+    scopes: Vec<ConsentScope>,
+}
+
+/// Everything the consent screen has to disclose before the user approves a client.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<OAuthRoutesState>,
+    Query(options): Query<ConsentOptions>
+) -> ResponseModel<ConsentResponse> {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    let client = match state.app.db.client.get(&options.client_id).await {
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return base::response::error(StatusCode::BAD_REQUEST, "Unknown client.", None);
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive client {}: {}", options.client_id, error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    let consent = match state.app.db.consent.get(&token.account_id, &client.client_id).await {
+        Ok(consent) => consent,
+        Err(error) => {
+            tracing::error!(
+                "Unable to retreive consent of {} for client {}: {}",
+                token.account_id,
+                client.client_id,
+                error
+            );
+            return base::response::internal_error(None);
+        }
+    };
+
+    let mut scopes = Vec::new();
+    for scope in parse_scope(&options.scope) {
+        if !client.scopes.contains(&scope) {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "The client is not allowed to request this scope.",
+                None
+            );
+        }
+
+        let sensitive = SENSITIVE_SCOPES.contains(&scope.as_str());
+        let purpose = client.purpose(&scope).map(|purpose| purpose.to_string());
+        if sensitive && purpose.is_none() {
+            return base::response::error(
+                StatusCode::BAD_REQUEST,
+                "The client didn't disclose why it needs this scope.",
+                None
+            );
+        }
+
+        scopes.push(ConsentScope {
+            granted: consent.as_ref().is_some_and(|consent| consent.scope.contains(&scope)),
+            scope,
+            purpose,
+            sensitive,
+        });
+    }
+
+    base::response::result(
+        StatusCode::OK,
+        ConsentResponse {
+            client_id: client.client_id,
+            name: client.name,
+            scopes,
+        },
+        None
+    )
+}
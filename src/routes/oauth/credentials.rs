@@ -0,0 +1,82 @@
+use axum::http::{ HeaderMap, StatusCode };
+
+use crate::{
+    AppState,
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::ClientDocument,
+    utils::oauth::basic_credentials,
+    workers::verify_pass::VerifyPassRequest,
+};
+
+/// Authenticates the client calling a back-channel endpoint, with either `client_secret_basic`
+/// or `client_secret_post`. Public clients only have to name themselves.
+pub async fn authenticate(
+    app: &AppState,
+    headers: &HeaderMap,
+    client_id: Option<&str>,
+    client_secret: Option<&str>
+) -> Result<ClientDocument, OAuthResponseModel> {
+    let (client_id, client_secret) = match basic_credentials(headers) {
+        Some((client_id, client_secret)) => (client_id, Some(client_secret)),
+        None => {
+            let Some(client_id) = client_id else {
+                return Err(
+                    oauth::error(StatusCode::UNAUTHORIZED, "invalid_client", "Unknown client.")
+                );
+            };
+            (client_id.to_string(), client_secret.map(|secret| secret.to_string()))
+        }
+    };
+
+    let client = match app.db.client.get(&client_id).await {
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return Err(oauth::error(StatusCode::UNAUTHORIZED, "invalid_client", "Unknown client."));
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive client {}: {}", client_id, error);
+            return Err(oauth::server_error());
+        }
+    };
+
+    let Some(secret_hash) = client.secret_hash.clone() else {
+        return match client_secret {
+            None => Ok(client),
+            Some(_) => {
+                Err(
+                    oauth::error(
+                        StatusCode::UNAUTHORIZED,
+                        "invalid_client",
+                        "Public clients must not send a secret."
+                    )
+                )
+            }
+        };
+    };
+
+    let Some(client_secret) = client_secret else {
+        return Err(
+            oauth::error(StatusCode::UNAUTHORIZED, "invalid_client", "Client secret required.")
+        );
+    };
+
+    let verify_pass_request = VerifyPassRequest {
+        password: client_secret,
+        hash: secret_hash,
+    };
+
+    match app.worker.verify_pass.send(verify_pass_request).await {
+        Ok(Ok(true)) => Ok(client),
+        Ok(Ok(false)) => {
+            Err(oauth::error(StatusCode::UNAUTHORIZED, "invalid_client", "Wrong client secret."))
+        }
+        Ok(Err(error)) => {
+            tracing::error!("Verify password worker failure for client {}: {error}", client_id);
+            Err(oauth::server_error())
+        }
+        Err(error) => {
+            tracing::error!("Unable to verify the secret of client {}: {error}", client_id);
+            Err(oauth::server_error())
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::BTreeMap;
+
+use axum::{ Extension, Json, extract::State, http::StatusCode };
+use mongodb::bson;
+use serde::Deserialize;
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    database::{ device_code::DeviceCodeStatus, pairwise::PairwiseDocument },
+    middlewares::auth::AuthorizationInfo,
+    routes::oauth::OAuthRoutesState,
+    utils::{
+        oauth::{ SENSITIVE_SCOPES, normalize_user_code },
+        oidc,
+        pairwise,
+        sudo,
+    },
+};
+
+#[derive(Deserialize)]
+pub struct ApprovePayload {
+    pub user_code: String,
+    /// `false` turns the device away.
+    pub approve: bool,
+    /// Proves the user just passed a second factor, reported to the client through `acr`/`amr`.
+    pub mfa_upgrade: Option<String>,
+}
+
+/// The device sees the outcome on its next poll of `/oauth/token`.
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<OAuthRoutesState>,
+    Json(payload): Json<ApprovePayload>
+) -> ResponseModel {
+    let Some(token) = authorization_info.token else {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    };
+
+    let (auth_time, amr) = match payload.mfa_upgrade {
+        Some(mfa_upgrade) => {
+            let redeemed = sudo::redeem_mfa_upgrade(&state.app, &token.account_id, &mfa_upgrade);
+            let mfa_upgrade = match redeemed.await {
+                Ok(Some(mfa_upgrade)) => mfa_upgrade,
+                Ok(None) => {
+                    return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to spend MFA upgrade for {}: {}",
+                        token.account_id,
+                        error
+                    );
+                    return base::response::internal_error(None);
+                }
+            };
+            (mfa_upgrade.iat, oidc::amr(&token.amr, true))
+        }
+        None => (token.auth_time, oidc::amr(&token.amr, false)),
+    };
+
+    let user_code = normalize_user_code(&payload.user_code);
+    let document = match state.app.db.device_code.get_from_user_code(&user_code).await {
+        Ok(Some(document)) if document.status == DeviceCodeStatus::Pending => document,
+        Ok(_) => {
+            return base::response::error(
+                StatusCode::NOT_FOUND,
+                "This code is invalid or expired.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive device code: {}", error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    let client = match payload.approve {
+        true => {
+            match state.app.db.client.get(&document.client_id).await {
+                Ok(Some(client)) => Some(client),
+                Ok(None) => {
+                    return base::response::error(StatusCode::BAD_REQUEST, "Unknown client.", None);
+                }
+                Err(error) => {
+                    tracing::error!("Unable to retreive client {}: {}", document.client_id, error);
+                    return base::response::internal_error(None);
+                }
+            }
+        }
+        false => None,
+    };
+
+    let mut purposes = BTreeMap::new();
+    if let Some(client) = &client {
+        for scope in document.scope.iter() {
+            if !SENSITIVE_SCOPES.contains(&scope.as_str()) {
+                continue;
+            }
+            let Some(purpose) = client.purpose(scope) else {
+                return base::response::error(
+                    StatusCode::BAD_REQUEST,
+                    "The client didn't disclose why it needs this scope.",
+                    None
+                );
+            };
+            purposes.insert(scope.clone(), purpose.to_string());
+        }
+    }
+
+    // Settled first, so consent is only recorded for a request this user actually approved.
+    let settled = state.app.db.device_code.settle(
+        &user_code,
+        payload.approve,
+        &token.account_id,
+        bson::DateTime::from_millis(auth_time.as_millis() as i64),
+        &amr
+    ).await;
+    match settled {
+        Ok(true) => {}
+        Ok(false) => {
+            return base::response::error(
+                StatusCode::NOT_FOUND,
+                "This code is invalid or expired.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to settle device code for {}: {}", token.account_id, error);
+            return base::response::internal_error(None);
+        }
+    }
+
+    let Some(client) = client else {
+        return base::response::success(StatusCode::OK, None);
+    };
+
+    let consent = state.app.db.consent.grant(
+        &token.account_id,
+        &client.client_id,
+        &document.scope,
+        &purposes
+    ).await;
+    if let Err(error) = consent {
+        tracing::error!(
+            "Unable to record consent of {} for client {}: {}",
+            token.account_id,
+            client.client_id,
+            error
+        );
+        return withdraw(&state, &user_code).await;
+    }
+
+    let pairwise_document = PairwiseDocument {
+        account_id: token.account_id.clone(),
+        client_id: client.client_id.clone(),
+        subject: pairwise::subject(&token.account_id, &client.sector_identifier),
+        issued_at: bson::DateTime::now(),
+    };
+
+    if let Err(error) = state.app.db.pairwise.record(&pairwise_document).await {
+        tracing::error!(
+            "Unable to record subject of {} for client {}: {}",
+            token.account_id,
+            client.client_id,
+            error
+        );
+        return withdraw(&state, &user_code).await;
+    }
+
+    base::response::success(StatusCode::OK, None)
+}
+
+/// The device is told the request was denied rather than polling for a grant that can't be used.
+async fn withdraw(state: &OAuthRoutesState, user_code: &str) -> ResponseModel {
+    if let Err(error) = state.app.db.device_code.withdraw(user_code).await {
+        tracing::error!("Unable to withdraw device code approval: {}", error);
+    }
+
+    base::response::internal_error(None)
+}
@@ -0,0 +1,56 @@
+use axum::{ Extension, extract::{ Query, State }, http::StatusCode };
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::{ self, response::ResponseModel },
+    middlewares::auth::AuthorizationInfo,
+    routes::oauth::OAuthRoutesState,
+    utils::oauth::normalize_user_code,
+};
+
+#[derive(Deserialize)]
+pub struct LookupOptions {
+    pub user_code: String,
+}
+
+#[derive(Serialize)]
+pub struct LookupResponse {
+    /// Pass both to `/oauth/consent` to render what the device asks for.
+    client_id: String,
+    scope: String,
+}
+
+pub async fn handler(
+    Extension(authorization_info): Extension<AuthorizationInfo>,
+    State(state): State<OAuthRoutesState>,
+    Query(options): Query<LookupOptions>
+) -> ResponseModel<LookupResponse> {
+    if authorization_info.token.is_none() {
+        return base::response::error(StatusCode::UNAUTHORIZED, "Get out.", None);
+    }
+
+    let user_code = normalize_user_code(&options.user_code);
+    let document = match state.app.db.device_code.get_from_user_code(&user_code).await {
+        Ok(Some(document)) => document,
+        Ok(None) => {
+            return base::response::error(
+                StatusCode::NOT_FOUND,
+                "This code is invalid or expired.",
+                None
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive device code: {}", error);
+            return base::response::internal_error(None);
+        }
+    };
+
+    base::response::result(
+        StatusCode::OK,
+        LookupResponse {
+            client_id: document.client_id,
+            scope: document.scope.join(" "),
+        },
+        None
+    )
+}
@@ -0,0 +1,23 @@
+use axum::{ Router, middleware };
+use axum::routing::get;
+
+use crate::{ middlewares::rate_limit, routes::oauth::OAuthRoutesState };
+
+mod lookup;
+mod approve;
+
+pub fn routes(state: OAuthRoutesState) -> Router<OAuthRoutesState> {
+    Router::new()
+        .route(
+            "/",
+            get(lookup::handler)
+                .post(approve::handler)
+                .layer(
+                    middleware::from_fn_with_state(
+                        (state.app.clone(), &rate_limit::DEVICE),
+                        rate_limit::limit
+                    )
+                )
+        )
+        .with_state(state)
+}
@@ -0,0 +1,106 @@
+use axum::{ Form, extract::State, http::{ HeaderMap, StatusCode } };
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::device_code::{ DeviceCodeDocument, DeviceCodeStatus },
+    env::{ DEVICE_CODE_MAX_AGE, DEVICE_PAGE, DEVICE_POLL_INTERVAL, OAUTH_CODE_LENGTH },
+    routes::oauth::{ OAuthRoutesState, credentials },
+    utils::oauth::{ DEVICE_CODE_GRANT, USER_CODE_ALPHABET, format_user_code, parse_scope },
+};
+
+#[derive(Deserialize)]
+pub struct DeviceAuthorizationPayload {
+    pub scope: String,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct DeviceAuthorizationResponse {
+    device_code: String,
+    user_code: String,
+    verification_uri: String,
+    verification_uri_complete: String,
+    expires_in: u64,
+    interval: u64,
+}
+
+/// RFC 8628, starts a sign in for a device that can't host a redirect.
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Form(payload): Form<DeviceAuthorizationPayload>
+) -> OAuthResponseModel {
+    let client = match
+        credentials::authenticate(
+            &state.app,
+            &headers,
+            payload.client_id.as_deref(),
+            payload.client_secret.as_deref()
+        ).await
+    {
+        Ok(client) => client,
+        Err(response) => {
+            return response;
+        }
+    };
+
+    if !client.grant_types.iter().any(|grant_type| grant_type == DEVICE_CODE_GRANT) {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+            "The client is not allowed to use this grant type."
+        );
+    }
+
+    let scope = parse_scope(&payload.scope);
+    if scope.is_empty() || scope.iter().any(|scope| !client.scopes.contains(scope)) {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_scope",
+            "The client is not registered for this scope."
+        );
+    }
+
+    let document = DeviceCodeDocument {
+        device_code: nanoid!(*OAUTH_CODE_LENGTH),
+        user_code: nanoid!(8, &USER_CODE_ALPHABET),
+        client_id: client.client_id,
+        scope,
+        status: DeviceCodeStatus::Pending,
+        account_id: None,
+        auth_time: None,
+        amr: Vec::new(),
+        interval: DEVICE_POLL_INTERVAL.as_secs(),
+        last_polled_at: None,
+        issued_at: bson::DateTime::now(),
+    };
+
+    match state.app.db.device_code.add(&document).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return oauth::server_error();
+        }
+        Err(error) => {
+            tracing::error!("Unable to store device code for {}: {}", document.client_id, error);
+            return oauth::server_error();
+        }
+    }
+
+    let user_code = format_user_code(&document.user_code);
+    let mut verification_uri_complete = DEVICE_PAGE.clone();
+    verification_uri_complete.query_pairs_mut().append_pair("user_code", &user_code);
+
+    oauth::result(DeviceAuthorizationResponse {
+        device_code: document.device_code,
+        user_code,
+        verification_uri: DEVICE_PAGE.to_string(),
+        verification_uri_complete: verification_uri_complete.into(),
+        expires_in: DEVICE_CODE_MAX_AGE.as_secs(),
+        interval: document.interval,
+    })
+}
@@ -0,0 +1,181 @@
+use axum::{ Form, extract::State, http::{ HeaderMap, StatusCode } };
+use mongodb::bson;
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::{ pairwise::PairwiseDocument, personal_token::TOKEN_PREFIX },
+    env::ISSUER,
+    routes::oauth::{ OAuthRoutesState, credentials },
+    utils::{ jwt::{ ActorClaims, KeyKind }, oauth::hash_token, pairwise },
+};
+
+#[derive(Deserialize)]
+pub struct IntrospectPayload {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct IntrospectResponse {
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_type: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iat: Option<u64>,
+    /// Pairwise to the sector of the client the token was issued to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aud: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iss: Option<String>,
+    /// Present on exchanged tokens, the chain of clients acting for the user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    act: Option<ActorClaims>,
+}
+
+/// RFC 7662, lets resource servers see revocations instead of only checking the signature.
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Form(payload): Form<IntrospectPayload>
+) -> OAuthResponseModel {
+    let client = match
+        credentials::authenticate(
+            &state.app,
+            &headers,
+            payload.client_id.as_deref(),
+            payload.client_secret.as_deref()
+        ).await
+    {
+        Ok(client) => client,
+        Err(response) => {
+            return response;
+        }
+    };
+
+    // A public client's ID is no secret, anyone could use it to test stolen tokens.
+    if !client.is_confidential() {
+        return oauth::error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_client",
+            "Only confidential clients can introspect tokens."
+        );
+    }
+
+    // Koii's own session tokens are never reported, they only ever reach Koii itself and would
+    // tell a client which of them are still good.
+    let kinds = match payload.token_type_hint.as_deref() {
+        Some("refresh_token") => [KeyKind::ClientRefresh, KeyKind::ClientAccess],
+        _ => [KeyKind::ClientAccess, KeyKind::ClientRefresh],
+    };
+
+    for kind in kinds {
+        let Some(claims) = state.app.jwt.verify_client(&payload.token, kind) else {
+            continue;
+        };
+
+        // Another client's subject would let the two link the account.
+        if claims.client_id != client.client_id {
+            return oauth::result(IntrospectResponse::default());
+        }
+
+        return match state.app.db.grant.clone().check(&claims).await {
+            Ok(true) => {
+                oauth::result(IntrospectResponse {
+                    active: true,
+                    scope: Some(claims.scope),
+                    token_type: Some("Bearer"),
+                    exp: Some(claims.exp.as_secs()),
+                    iat: Some(claims.iat.as_secs()),
+                    sub: Some(claims.sub),
+                    aud: Some(claims.client_id.clone()),
+                    client_id: Some(claims.client_id),
+                    iss: Some(ISSUER.clone()),
+                    act: claims.act,
+                })
+            }
+            Ok(false) => oauth::result(IntrospectResponse::default()),
+            Err(error) => {
+                tracing::error!("Failed to query grant {}: {error}", claims.identifier);
+                oauth::server_error()
+            }
+        };
+    }
+
+    // Service tokens carry no user, any resource server may look at them.
+    if let Some(claims) = state.app.jwt.verify_client(&payload.token, KeyKind::Service) {
+        return oauth::result(IntrospectResponse {
+            active: true,
+            scope: Some(claims.scope),
+            token_type: Some("Bearer"),
+            exp: Some(claims.exp.as_secs()),
+            iat: Some(claims.iat.as_secs()),
+            sub: Some(claims.sub),
+            aud: Some(claims.client_id.clone()),
+            client_id: Some(claims.client_id),
+            iss: Some(ISSUER.clone()),
+            ..IntrospectResponse::default()
+        });
+    }
+
+    // Personal access tokens are sent to resource servers by the user's own scripts.
+    if payload.token.starts_with(TOKEN_PREFIX) {
+        let token_hash = hash_token(&payload.token);
+        let personal = match state.app.db.personal_token.use_token(&token_hash).await {
+            Ok(Some(personal)) => personal,
+            Ok(None) => {
+                return oauth::result(IntrospectResponse::default());
+            }
+            Err(error) => {
+                tracing::error!("Failed to query database for a personal access token: {error}");
+                return oauth::server_error();
+            }
+        };
+
+        // The resource server learns who the user is, like any client they signed in to.
+        let sub = pairwise::subject(&personal.account_id, &client.sector_identifier);
+        let pairwise_document = PairwiseDocument {
+            account_id: personal.account_id.clone(),
+            client_id: client.client_id.clone(),
+            subject: sub.clone(),
+            issued_at: bson::DateTime::now(),
+        };
+
+        if let Err(error) = state.app.db.pairwise.record(&pairwise_document).await {
+            tracing::error!(
+                "Unable to record subject of {} for client {}: {}",
+                personal.account_id,
+                client.client_id,
+                error
+            );
+            return oauth::server_error();
+        }
+
+        return oauth::result(IntrospectResponse {
+            active: true,
+            scope: Some(personal.scopes.join(" ")),
+            token_type: Some("Bearer"),
+            exp: personal.expires_at.map(|expires_at| seconds(expires_at)),
+            iat: Some(seconds(personal.issued_at)),
+            sub: Some(sub),
+            iss: Some(ISSUER.clone()),
+            ..IntrospectResponse::default()
+        });
+    }
+
+    oauth::result(IntrospectResponse::default())
+}
+
+fn seconds(time: bson::DateTime) -> u64 {
+    (time.timestamp_millis() / 1000) as u64
+}
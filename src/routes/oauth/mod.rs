@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::{ Router, middleware, routing::{ get, post } };
+
+use crate::{ AppState, middlewares::{ auth, rate_limit } };
+
+mod credentials;
+mod authorize;
+mod consent;
+mod token;
+mod userinfo;
+mod introspect;
+mod revoke;
+mod device_authorization;
+mod device;
+mod register;
+
+#[derive(Clone)]
+pub struct OAuthRoutesState {
+    pub app: Arc<AppState>,
+}
+
+pub fn routes(app_state: Arc<AppState>) -> Router {
+    let state = OAuthRoutesState {
+        app: app_state,
+    };
+
+    Router::new()
+        .route("/authorize", post(authorize::handler))
+        .route("/consent", get(consent::handler))
+        .route(
+            "/token",
+            post(token::handler).layer(
+                middleware::from_fn_with_state(
+                    (state.app.clone(), &rate_limit::TOKEN),
+                    rate_limit::limit
+                )
+            )
+        )
+        .route("/userinfo", get(userinfo::handler).post(userinfo::handler))
+        .route("/introspect", post(introspect::handler))
+        .route("/revoke", post(revoke::handler))
+        .route("/device_authorization", post(device_authorization::handler))
+        .nest("/device", device::routes(state.clone()))
+        .nest("/register", register::routes(state.clone()))
+        .layer(middleware::from_fn_with_state(state.app.clone(), auth::authorize))
+        .with_state(state)
+}
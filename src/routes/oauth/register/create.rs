@@ -0,0 +1,108 @@
+use axum::{ Json, extract::State, http::{ HeaderMap, StatusCode } };
+use nanoid::nanoid;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::ClientMetadataError,
+    env::{ CLIENT_ID_LENGTH, CLIENT_SECRET_LENGTH, REGISTRATION_SCOPES, REGISTRATION_TOKEN },
+    routes::oauth::{
+        OAuthRoutesState,
+        register::{
+            ClientInformation,
+            ClientMetadata,
+            REGISTRATION_GRANT_TYPES,
+            blank,
+            metadata_error,
+            narrows,
+        },
+    },
+    utils::oauth::{ bearer_token, hash_token },
+};
+
+/// RFC 7591, registration is closed to anyone without the initial access token.
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Json(metadata): Json<ClientMetadata>
+) -> OAuthResponseModel {
+    let authorized = match bearer_token(&headers) {
+        Some(initial_token) if !REGISTRATION_TOKEN.is_empty() => {
+            hash_token(initial_token) == hash_token(&REGISTRATION_TOKEN)
+        }
+        _ => false,
+    };
+
+    if !authorized {
+        return oauth::error(
+            StatusCode::UNAUTHORIZED,
+            "invalid_token",
+            "Invalid initial access token."
+        );
+    }
+
+    let Some(confidential) = metadata.is_confidential() else {
+        return metadata_error(
+            ClientMetadataError::Metadata("Unsupported token endpoint authentication method.")
+        );
+    };
+
+    let mut client = blank(nanoid!(*CLIENT_ID_LENGTH));
+    let client_secret = confidential.then(|| nanoid!(*CLIENT_SECRET_LENGTH));
+    let registration_token = nanoid!(*CLIENT_SECRET_LENGTH);
+
+    if let Some(client_secret) = &client_secret {
+        client.secret_hash = match state.app.worker.hash_pass.send(client_secret.clone()).await {
+            Ok(Ok(hash)) => Some(hash),
+            Ok(Err(error)) => {
+                tracing::error!("Hash password worker failed when registering a client: {error}");
+                return oauth::server_error();
+            }
+            Err(error) => {
+                tracing::error!("Unable to hash a client secret: {error}");
+                return oauth::server_error();
+            }
+        };
+    }
+    client.registration_token_hash = Some(hash_token(&registration_token));
+
+    if let Err(error) = metadata.apply(&mut client) {
+        return metadata_error(error);
+    }
+
+    if !narrows(&REGISTRATION_SCOPES, &client.scopes) {
+        return metadata_error(
+            ClientMetadataError::Metadata("A scope is not open to dynamic registration.")
+        );
+    }
+
+    let open = |grant_type: &String| REGISTRATION_GRANT_TYPES.contains(&grant_type.as_str());
+    if !client.grant_types.iter().all(open) {
+        return metadata_error(
+            ClientMetadataError::Metadata("A grant type is not open to dynamic registration.")
+        );
+    }
+
+    if !client.delegation_targets.is_empty() {
+        return metadata_error(
+            ClientMetadataError::Metadata("Delegation targets are set by the operator.")
+        );
+    }
+
+    match state.app.db.client.add(&client).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return oauth::server_error();
+        }
+        Err(error) => {
+            tracing::error!("Unable to register client {}: {}", client.client_id, error);
+            return oauth::server_error();
+        }
+    }
+
+    oauth::created(ClientInformation {
+        client_secret,
+        registration_access_token: Some(registration_token),
+        ..ClientInformation::from(client)
+    })
+}
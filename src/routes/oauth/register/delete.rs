@@ -0,0 +1,39 @@
+use axum::{ extract::{ Path, State }, http::{ HeaderMap, StatusCode } };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    routes::oauth::{ OAuthRoutesState, register::authenticate },
+};
+
+/// Removes the client, every token it still holds and what users agreed to share with it, for
+/// when a preview environment goes away.
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>
+) -> Result<StatusCode, OAuthResponseModel> {
+    let client = authenticate(&state, &headers, &client_id).await?;
+
+    if let Err(error) = state.app.db.client.remove(&client.client_id).await {
+        tracing::error!("Unable to remove client {}: {}", client.client_id, error);
+        return Err(oauth::server_error());
+    }
+
+    if let Err(error) = state.app.db.grant.clone().revoke_issued_to(&client.client_id).await {
+        tracing::error!("Unable to revoke grants of client {}: {}", client.client_id, error);
+        return Err(oauth::server_error());
+    }
+
+    // A client registered later with the same ID must not inherit any of it.
+    if let Err(error) = state.app.db.consent.revoke_client(&client.client_id).await {
+        tracing::error!("Unable to remove consents to client {}: {}", client.client_id, error);
+        return Err(oauth::server_error());
+    }
+
+    if let Err(error) = state.app.db.pairwise.remove_client(&client.client_id).await {
+        tracing::error!("Unable to remove subjects of client {}: {}", client.client_id, error);
+        return Err(oauth::server_error());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,201 @@
+use std::collections::BTreeMap;
+
+use axum::{ Router, http::{ HeaderMap, StatusCode }, routing::{ get, post } };
+use mongodb::bson;
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::{ ClientDocument, ClientMetadataError },
+    env::ISSUER,
+    routes::oauth::OAuthRoutesState,
+    utils::oauth::{ bearer_token, hash_token, parse_scope },
+};
+
+mod create;
+mod read;
+mod update;
+mod delete;
+
+pub fn routes(state: OAuthRoutesState) -> Router<OAuthRoutesState> {
+    Router::new()
+        .route("/", post(create::handler))
+        .route("/{client_id}", get(read::handler).put(update::handler).delete(delete::handler))
+        .with_state(state)
+}
+
+/// RFC 7591 client metadata, plus the Koii specific purposes and delegations.
+#[derive(Deserialize)]
+pub struct ClientMetadata {
+    /// Only read on updates, where it has to match the client being updated.
+    pub client_id: Option<String>,
+    pub client_name: String,
+    #[serde(default)]
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Option<Vec<String>>,
+    pub scope: String,
+    pub token_endpoint_auth_method: Option<String>,
+    #[serde(default)]
+    pub purposes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub delegation_targets: Vec<String>,
+    #[serde(default)]
+    pub delegation_actors: Vec<String>,
+    pub backchannel_logout_uri: Option<String>,
+}
+
+impl ClientMetadata {
+    /// Secrets are accepted both ways at the token endpoint, so both methods mean the same.
+    /// `None` for any other method.
+    pub fn is_confidential(&self) -> Option<bool> {
+        match self.token_endpoint_auth_method.as_deref() {
+            None | Some("client_secret_basic") | Some("client_secret_post") => Some(true),
+            Some("none") => Some(false),
+            Some(_) => None,
+        }
+    }
+
+    /// Fills a client with this metadata, held to the same rules as clients registered by hand.
+    pub fn apply(self, client: &mut ClientDocument) -> Result<(), ClientMetadataError> {
+        client.name = self.client_name;
+        client.redirect_uris = self.redirect_uris;
+        client.scopes = parse_scope(&self.scope);
+        client.purposes = self.purposes;
+        client.delegation_targets = self.delegation_targets;
+        client.delegation_actors = self.delegation_actors;
+        client.backchannel_logout_uri = self.backchannel_logout_uri;
+        if let Some(grant_types) = self.grant_types {
+            client.grant_types = grant_types;
+        }
+
+        client.validate()
+    }
+}
+
+/// Grant types a dynamically registered client may use, service tokens and token exchange are
+/// left for the operator to hand out.
+pub const REGISTRATION_GRANT_TYPES: [&str; 2] = ["authorization_code", "refresh_token"];
+
+/// Whether `next` only keeps values from `previous`, what a client may grant itself can only
+/// shrink without the operator.
+pub fn narrows(previous: &[String], next: &[String]) -> bool {
+    next.iter().all(|value| previous.contains(value))
+}
+
+pub fn metadata_error(error: ClientMetadataError) -> OAuthResponseModel {
+    match error {
+        ClientMetadataError::RedirectUri(description) => {
+            oauth::error(StatusCode::BAD_REQUEST, "invalid_redirect_uri", description)
+        }
+        ClientMetadataError::Metadata(description) => {
+            oauth::error(StatusCode::BAD_REQUEST, "invalid_client_metadata", description)
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ClientInformation {
+    pub client_id: String,
+    /// Only shown once, when the client is registered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+    pub client_id_issued_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_secret_expires_at: Option<u64>,
+    /// Only shown once, when the client is registered.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration_access_token: Option<String>,
+    pub registration_client_uri: String,
+    pub client_name: String,
+    pub redirect_uris: Vec<String>,
+    pub grant_types: Vec<String>,
+    pub scope: String,
+    pub token_endpoint_auth_method: &'static str,
+    pub purposes: BTreeMap<String, String>,
+    pub delegation_targets: Vec<String>,
+    pub delegation_actors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backchannel_logout_uri: Option<String>,
+}
+
+impl From<ClientDocument> for ClientInformation {
+    fn from(client: ClientDocument) -> Self {
+        let confidential = client.is_confidential();
+
+        ClientInformation {
+            registration_client_uri: format!("{}/oauth/register/{}", *ISSUER, client.client_id),
+            client_id: client.client_id,
+            client_secret: None,
+            client_id_issued_at: client.issued_at.timestamp_millis() / 1000,
+            // Secrets never expire.
+            client_secret_expires_at: confidential.then_some(0),
+            registration_access_token: None,
+            client_name: client.name,
+            redirect_uris: client.redirect_uris,
+            grant_types: client.grant_types,
+            scope: client.scopes.join(" "),
+            token_endpoint_auth_method: match confidential {
+                true => "client_secret_basic",
+                false => "none",
+            },
+            purposes: client.purposes,
+            delegation_targets: client.delegation_targets,
+            delegation_actors: client.delegation_actors,
+            backchannel_logout_uri: client.backchannel_logout_uri,
+        }
+    }
+}
+
+/// Checks the registration access token, RFC 7592 answers the same whether the client
+/// exists or not.
+pub async fn authenticate(
+    state: &OAuthRoutesState,
+    headers: &HeaderMap,
+    client_id: &str
+) -> Result<ClientDocument, OAuthResponseModel> {
+    let invalid_token = || {
+        oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Invalid registration token.")
+    };
+
+    let Some(registration_token) = bearer_token(headers) else {
+        return Err(invalid_token());
+    };
+
+    let client = match state.app.db.client.get(client_id).await {
+        Ok(Some(client)) => client,
+        Ok(None) => {
+            return Err(invalid_token());
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive client {}: {}", client_id, error);
+            return Err(oauth::server_error());
+        }
+    };
+
+    match &client.registration_token_hash {
+        Some(registration_token_hash) if
+            *registration_token_hash == hash_token(registration_token)
+        => Ok(client),
+        _ => Err(invalid_token()),
+    }
+}
+
+/// A blank client to fill with metadata, dynamic clients get a sector of their own so they
+/// can never line up subjects with another client.
+pub fn blank(client_id: String) -> ClientDocument {
+    ClientDocument {
+        sector_identifier: client_id.clone(),
+        client_id,
+        name: String::new(),
+        secret_hash: None,
+        redirect_uris: Vec::new(),
+        scopes: Vec::new(),
+        grant_types: vec!["authorization_code".to_string(), "refresh_token".to_string()],
+        purposes: BTreeMap::new(),
+        delegation_targets: Vec::new(),
+        delegation_actors: Vec::new(),
+        backchannel_logout_uri: None,
+        registration_token_hash: None,
+        issued_at: bson::DateTime::now(),
+    }
+}
@@ -0,0 +1,17 @@
+use axum::{ extract::{ Path, State }, http::HeaderMap };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    routes::oauth::{ OAuthRoutesState, register::{ ClientInformation, authenticate } },
+};
+
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>
+) -> OAuthResponseModel {
+    match authenticate(&state, &headers, &client_id).await {
+        Ok(client) => oauth::result(ClientInformation::from(client)),
+        Err(response) => response,
+    }
+}
@@ -0,0 +1,70 @@
+use axum::{ Json, extract::{ Path, State }, http::{ HeaderMap, StatusCode } };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::ClientMetadataError,
+    routes::oauth::{
+        OAuthRoutesState,
+        register::{ ClientInformation, ClientMetadata, authenticate, metadata_error, narrows },
+    },
+};
+
+/// RFC 7592, the metadata sent replaces the current one as a whole.
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Path(client_id): Path<String>,
+    Json(metadata): Json<ClientMetadata>
+) -> OAuthResponseModel {
+    let mut client = match authenticate(&state, &headers, &client_id).await {
+        Ok(client) => client,
+        Err(response) => {
+            return response;
+        }
+    };
+
+    if metadata.client_id.as_ref().is_some_and(|id| *id != client.client_id) {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_client_metadata",
+            "The client ID doesn't match."
+        );
+    }
+
+    // Switching would mean minting or dropping a secret, register a new client instead.
+    if metadata.is_confidential() != Some(client.is_confidential()) {
+        return metadata_error(
+            ClientMetadataError::Metadata(
+                "The token endpoint authentication method can't be changed."
+            )
+        );
+    }
+
+    let previous = client.clone();
+    if let Err(error) = metadata.apply(&mut client) {
+        return metadata_error(error);
+    }
+
+    if
+        !narrows(&previous.scopes, &client.scopes) ||
+        !narrows(&previous.grant_types, &client.grant_types) ||
+        !narrows(&previous.delegation_targets, &client.delegation_targets)
+    {
+        return metadata_error(
+            ClientMetadataError::Metadata(
+                "Only the operator widens scopes, grant types and delegation targets."
+            )
+        );
+    }
+
+    match state.app.db.client.update(&client).await {
+        Ok(true) => oauth::result(ClientInformation::from(client)),
+        Ok(false) => {
+            oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Invalid registration token.")
+        }
+        Err(error) => {
+            tracing::error!("Unable to update client {}: {}", client.client_id, error);
+            oauth::server_error()
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use axum::{ Form, extract::State, http::{ HeaderMap, StatusCode } };
+use serde::Deserialize;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    routes::oauth::{ OAuthRoutesState, credentials },
+    utils::jwt::KeyKind,
+};
+
+#[derive(Deserialize)]
+pub struct RevokePayload {
+    pub token: String,
+    pub token_type_hint: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// RFC 7009, clients can only revoke tokens issued to themselves.
+///
+/// Unknown, invalid or foreign tokens still get a success, so the endpoint can't be used to probe.
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Form(payload): Form<RevokePayload>
+) -> OAuthResponseModel {
+    let client = match
+        credentials::authenticate(
+            &state.app,
+            &headers,
+            payload.client_id.as_deref(),
+            payload.client_secret.as_deref()
+        ).await
+    {
+        Ok(client) => client,
+        Err(response) => {
+            return response;
+        }
+    };
+
+    let kinds = match payload.token_type_hint.as_deref() {
+        Some("access_token") => [KeyKind::ClientAccess, KeyKind::ClientRefresh],
+        _ => [KeyKind::ClientRefresh, KeyKind::ClientAccess],
+    };
+
+    let claims = kinds
+        .into_iter()
+        .find_map(|kind| state.app.jwt.verify_client(&payload.token, kind))
+        .filter(|claims| claims.client_id == client.client_id);
+
+    // Access and refresh token share the grant, revoking one revokes both.
+    if let Some(claims) = claims {
+        match state.app.db.grant.clone().revoke(&claims.identifier).await {
+            Ok(_) => {}
+            Err(error) => {
+                tracing::error!("Unable to revoke grant {}: {error}", claims.identifier);
+                return oauth::error(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "temporarily_unavailable",
+                    "The token could not be revoked, try again later."
+                );
+            }
+        }
+    }
+
+    oauth::result(serde_json::json!({}))
+}
@@ -0,0 +1,69 @@
+use axum::http::StatusCode;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::ClientDocument,
+    routes::oauth::{ OAuthRoutesState, token::{ Authorization, TokenPayload, issue } },
+    utils::oauth::verify_pkce,
+};
+
+pub async fn handler(
+    state: OAuthRoutesState,
+    client: ClientDocument,
+    payload: TokenPayload
+) -> OAuthResponseModel {
+    let (Some(code), Some(redirect_uri)) = (payload.code, payload.redirect_uri) else {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "Both `code` and `redirect_uri` are required."
+        );
+    };
+
+    let code_document = match state.app.db.authorization_code.consume(&code).await {
+        Ok(Some(code_document)) => code_document,
+        Ok(None) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_grant",
+                "The code is invalid or expired."
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to consume authorization code: {}", error);
+            return oauth::server_error();
+        }
+    };
+
+    if code_document.client_id != client.client_id || code_document.redirect_uri != redirect_uri {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_grant",
+            "The code is invalid or expired."
+        );
+    }
+
+    match (&code_document.code_challenge, &payload.code_verifier) {
+        (None, None) => {}
+        (Some(code_challenge), Some(code_verifier)) if
+            verify_pkce(code_verifier, code_challenge)
+        => {}
+        _ => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_grant",
+                "PKCE verification failed."
+            );
+        }
+    }
+
+    let authorization = Authorization {
+        account_id: code_document.account_id,
+        scope: code_document.scope,
+        auth_time: code_document.auth_time,
+        amr: code_document.amr,
+        nonce: code_document.nonce,
+    };
+
+    issue(&state, &client, authorization).await
+}
@@ -0,0 +1,82 @@
+use axum::http::StatusCode;
+use nanoid::nanoid;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::ClientDocument,
+    env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, TOKEN_MAX_AGE },
+    routes::oauth::{ OAuthRoutesState, token::{ TokenPayload, TokenResponse } },
+    utils::{ jwt::{ ClientClaims, KeyKind }, oauth::{ USER_SCOPES, parse_scope }, timestamp },
+};
+
+/// Service tokens are short lived and never come with a refresh token, the client can simply
+/// authenticate again.
+pub async fn handler(
+    state: OAuthRoutesState,
+    client: ClientDocument,
+    payload: TokenPayload
+) -> OAuthResponseModel {
+    if !client.is_confidential() {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+            "Only confidential clients can act on their own behalf."
+        );
+    }
+
+    let scope = match payload.scope {
+        Some(scope) => {
+            let scope = parse_scope(&scope);
+            let allowed = |scope: &String| {
+                client.scopes.contains(scope) && !USER_SCOPES.contains(&scope.as_str())
+            };
+            if scope.is_empty() || !scope.iter().all(allowed) {
+                return oauth::error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_scope",
+                    "The client is not registered for this scope."
+                );
+            }
+            scope
+        }
+        None => {
+            client.scopes
+                .iter()
+                .filter(|scope| !USER_SCOPES.contains(&scope.as_str()))
+                .cloned()
+                .collect()
+        }
+    };
+
+    if scope.is_empty() {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_scope",
+            "The client is not registered for any service scope."
+        );
+    }
+
+    let issued_at = timestamp::now();
+    let scope = scope.join(" ");
+
+    let signed_service = state.app.jwt.generate_client(ClientClaims {
+        sub: client.client_id.clone(),
+        client_id: client.client_id,
+        scope: scope.clone(),
+        identifier: nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH),
+        kind: KeyKind::Service,
+        iat: issued_at,
+        exp: issued_at + *TOKEN_MAX_AGE,
+        act: None,
+    });
+
+    oauth::result(TokenResponse {
+        access_token: signed_service,
+        token_type: "Bearer",
+        expires_in: TOKEN_MAX_AGE.as_secs(),
+        refresh_token: None,
+        id_token: None,
+        issued_token_type: None,
+        scope,
+    })
+}
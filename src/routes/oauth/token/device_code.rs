@@ -0,0 +1,111 @@
+use axum::http::StatusCode;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::{ client::ClientDocument, device_code::DeviceCodeStatus },
+    routes::oauth::{ OAuthRoutesState, token::{ Authorization, TokenPayload, issue } },
+};
+
+pub async fn handler(
+    state: OAuthRoutesState,
+    client: ClientDocument,
+    payload: TokenPayload
+) -> OAuthResponseModel {
+    let Some(device_code) = payload.device_code else {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "`device_code` is required."
+        );
+    };
+
+    let document = match state.app.db.device_code.poll(&device_code, &client.client_id).await {
+        Ok(Some(document)) if !document.is_expired() => document,
+        Ok(Some(_)) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "expired_token",
+                "The device code has expired."
+            );
+        }
+        Ok(None) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_grant",
+                "Unknown device code."
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to poll device code for {}: {}", client.client_id, error);
+            return oauth::server_error();
+        }
+    };
+
+    if let Some(last_polled_at) = document.last_polled_at {
+        let elapsed = last_polled_at.to_system_time().elapsed().unwrap_or_default();
+        if elapsed.as_secs() < document.interval {
+            if let Err(error) = state.app.db.device_code.slow_down(&device_code).await {
+                tracing::error!(
+                    "Unable to slow down device code for {}: {}",
+                    client.client_id,
+                    error
+                );
+                return oauth::server_error();
+            }
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "slow_down",
+                "Polling too fast, wait 5 more seconds between requests."
+            );
+        }
+    }
+
+    match document.status {
+        DeviceCodeStatus::Pending => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "authorization_pending",
+                "The user hasn't approved the request yet."
+            );
+        }
+        DeviceCodeStatus::Denied => {
+            let _ = state.app.db.device_code.consume(&device_code).await;
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "access_denied",
+                "The user denied the request."
+            );
+        }
+        DeviceCodeStatus::Approved => {}
+    }
+
+    match state.app.db.device_code.consume(&device_code).await {
+        Ok(true) => {}
+        // Another poll redeemed it first.
+        Ok(false) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_grant",
+                "The device code was already used."
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to consume device code for {}: {}", client.client_id, error);
+            return oauth::server_error();
+        }
+    }
+
+    let (Some(account_id), Some(auth_time)) = (document.account_id, document.auth_time) else {
+        return oauth::server_error();
+    };
+
+    let authorization = Authorization {
+        account_id,
+        scope: document.scope,
+        auth_time,
+        amr: document.amr,
+        nonce: None,
+    };
+
+    issue(&state, &client, authorization).await
+}
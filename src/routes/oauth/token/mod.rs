@@ -0,0 +1,253 @@
+use std::time::Duration;
+
+use axum::{ Form, extract::State, http::{ HeaderMap, StatusCode } };
+use mongodb::bson;
+use nanoid::nanoid;
+use serde::{ Deserialize, Serialize };
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::{ client::ClientDocument, grant::GrantDocument },
+    env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, ISSUER, REFRESH_MAX_AGE, TOKEN_MAX_AGE },
+    routes::oauth::{ OAuthRoutesState, credentials },
+    utils::{
+        jwt::{ ClientClaims, IdTokenClaims, KeyKind },
+        oauth::{ DEVICE_CODE_GRANT, TOKEN_EXCHANGE_GRANT },
+        oidc,
+        pairwise,
+        timestamp,
+    },
+};
+
+mod authorization_code;
+mod refresh_token;
+mod client_credentials;
+mod device_code;
+mod token_exchange;
+
+#[derive(Deserialize)]
+pub struct TokenPayload {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub device_code: Option<String>,
+    pub scope: Option<String>,
+    pub subject_token: Option<String>,
+    pub subject_token_type: Option<String>,
+    pub audience: Option<String>,
+    pub requested_token_type: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issued_token_type: Option<&'static str>,
+    pub scope: String,
+}
+
+/// What the user approved, gathered from either an authorization code or a previous grant.
+pub struct Authorization {
+    pub account_id: String,
+    pub scope: Vec<String>,
+    pub auth_time: bson::DateTime,
+    pub amr: Vec<String>,
+    pub nonce: Option<String>,
+}
+
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap,
+    Form(payload): Form<TokenPayload>
+) -> OAuthResponseModel {
+    let client = match
+        credentials::authenticate(
+            &state.app,
+            &headers,
+            payload.client_id.as_deref(),
+            payload.client_secret.as_deref()
+        ).await
+    {
+        Ok(client) => client,
+        Err(response) => {
+            return response;
+        }
+    };
+
+    if !client.grant_types.contains(&payload.grant_type) {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+            "The client is not allowed to use this grant type."
+        );
+    }
+
+    match payload.grant_type.as_str() {
+        "authorization_code" => authorization_code::handler(state, client, payload).await,
+        "refresh_token" => refresh_token::handler(state, client, payload).await,
+        "client_credentials" => client_credentials::handler(state, client, payload).await,
+        DEVICE_CODE_GRANT => device_code::handler(state, client, payload).await,
+        TOKEN_EXCHANGE_GRANT => token_exchange::handler(state, client, payload).await,
+        _ => {
+            oauth::error(
+                StatusCode::BAD_REQUEST,
+                "unsupported_grant_type",
+                "This grant type is not supported."
+            )
+        }
+    }
+}
+
+/// Issues a new grant with an access and refresh token pair for the client, plus an ID token
+/// when `openid` was granted.
+async fn issue(
+    state: &OAuthRoutesState,
+    client: &ClientDocument,
+    authorization: Authorization
+) -> OAuthResponseModel {
+    // The user may have revoked the client while a code or refresh was still around.
+    let consent = state.app.db.consent.get(&authorization.account_id, &client.client_id).await;
+    match consent {
+        Ok(Some(consent)) if
+            authorization.scope.iter().all(|scope| consent.scope.contains(scope))
+        => {}
+        Ok(_) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_grant",
+                "The user no longer consents to this scope."
+            );
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to retreive consent of {} for client {}: {}",
+                authorization.account_id,
+                client.client_id,
+                error
+            );
+            return oauth::server_error();
+        }
+    }
+
+    let issued_at = timestamp::now();
+    let identifier = nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH);
+    let subject = pairwise::subject(&authorization.account_id, &client.sector_identifier);
+
+    let id_token = match authorization.scope.iter().any(|scope| scope == "openid") {
+        true => {
+            let account = match
+                state.app.db.account.get_active_from_id(&authorization.account_id).await
+            {
+                Ok(Some(account)) => account,
+                Ok(None) => {
+                    return oauth::error(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_grant",
+                        "The account is currently on hold."
+                    );
+                }
+                Err(error) => {
+                    tracing::error!(
+                        "Unable to retreive account for {}: {}",
+                        authorization.account_id,
+                        error
+                    );
+                    return oauth::server_error();
+                }
+            };
+
+            let email_granted = authorization.scope.iter().any(|scope| scope == "email");
+
+            Some(
+                state.app.jwt.generate_id_token(IdTokenClaims {
+                    iss: ISSUER.clone(),
+                    sub: subject.clone(),
+                    aud: client.client_id.clone(),
+                    iat: issued_at,
+                    exp: issued_at + *TOKEN_MAX_AGE,
+                    auth_time: Duration::from_millis(
+                        authorization.auth_time.timestamp_millis() as u64
+                    ),
+                    nonce: authorization.nonce,
+                    acr: oidc::acr(&authorization.amr).to_string(),
+                    amr: authorization.amr.clone(),
+                    email: email_granted.then_some(account.email),
+                    email_verified: email_granted.then_some(account.issued_at.is_some()),
+                })
+            )
+        }
+        false => None,
+    };
+
+    let scope = authorization.scope;
+    let grant = GrantDocument {
+        identifier: identifier.clone(),
+        account_id: authorization.account_id,
+        client_id: client.client_id.clone(),
+        scope: scope.clone(),
+        auth_time: authorization.auth_time,
+        amr: authorization.amr,
+        issued_at: bson::DateTime::from_millis(issued_at.as_millis() as i64),
+    };
+
+    match state.app.db.grant.clone().issue(&grant).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return oauth::server_error();
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to issue a grant for {} to client {}: {}",
+                grant.account_id,
+                grant.client_id,
+                error
+            );
+            return oauth::server_error();
+        }
+    }
+
+    let scope = scope.join(" ");
+
+    let signed_access = state.app.jwt.generate_client(ClientClaims {
+        sub: subject.clone(),
+        client_id: client.client_id.clone(),
+        scope: scope.clone(),
+        identifier: identifier.clone(),
+        kind: KeyKind::ClientAccess,
+        iat: issued_at,
+        exp: issued_at + *TOKEN_MAX_AGE,
+        act: None,
+    });
+
+    let signed_refresh = state.app.jwt.generate_client(ClientClaims {
+        sub: subject,
+        client_id: client.client_id.clone(),
+        scope: scope.clone(),
+        identifier,
+        kind: KeyKind::ClientRefresh,
+        iat: issued_at,
+        exp: issued_at + *REFRESH_MAX_AGE,
+        act: None,
+    });
+
+    oauth::result(TokenResponse {
+        access_token: signed_access,
+        token_type: "Bearer",
+        expires_in: TOKEN_MAX_AGE.as_secs(),
+        refresh_token: Some(signed_refresh),
+        id_token,
+        issued_token_type: None,
+        scope,
+    })
+}
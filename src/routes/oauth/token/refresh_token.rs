@@ -0,0 +1,92 @@
+use axum::http::StatusCode;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::client::ClientDocument,
+    routes::oauth::{ OAuthRoutesState, token::{ Authorization, TokenPayload, issue } },
+    utils::{ jwt::KeyKind, oauth::parse_scope },
+};
+
+pub async fn handler(
+    state: OAuthRoutesState,
+    client: ClientDocument,
+    payload: TokenPayload
+) -> OAuthResponseModel {
+    let Some(refresh_token) = payload.refresh_token else {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "`refresh_token` is required."
+        );
+    };
+
+    let Some(revoking_refresh) = state.app.jwt.verify_client(
+        &refresh_token,
+        KeyKind::ClientRefresh
+    ) else {
+        return oauth::error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid refresh token.");
+    };
+
+    if revoking_refresh.client_id != client.client_id {
+        return oauth::error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid refresh token.");
+    }
+
+    match state.app.db.grant.clone().check(&revoking_refresh).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return oauth::error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid refresh token.");
+        }
+        Err(error) => {
+            tracing::error!("Failed to query grant {}: {error}", revoking_refresh.identifier);
+            return oauth::server_error();
+        }
+    }
+
+    let grant = match state.app.db.grant.get(&revoking_refresh.identifier).await {
+        Ok(Some(grant)) => grant,
+        Ok(None) => {
+            return oauth::error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid refresh token.");
+        }
+        Err(error) => {
+            tracing::error!("Failed to query grant {}: {error}", revoking_refresh.identifier);
+            return oauth::server_error();
+        }
+    };
+
+    // A refresh may narrow the scope, never widen it.
+    let scope = match payload.scope {
+        Some(scope) => {
+            let scope = parse_scope(&scope);
+            if scope.is_empty() || scope.iter().any(|scope| !grant.scope.contains(scope)) {
+                return oauth::error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_scope",
+                    "The scope exceeds what was granted."
+                );
+            }
+            scope
+        }
+        None => grant.scope,
+    };
+
+    match state.app.db.grant.clone().revoke(&revoking_refresh.identifier).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return oauth::error(StatusCode::BAD_REQUEST, "invalid_grant", "Invalid refresh token.");
+        }
+        Err(error) => {
+            tracing::error!("Unable to revoke grant {}: {error}", revoking_refresh.identifier);
+            return oauth::server_error();
+        }
+    }
+
+    let authorization = Authorization {
+        account_id: grant.account_id,
+        scope,
+        auth_time: grant.auth_time,
+        amr: grant.amr,
+        nonce: None,
+    };
+
+    issue(&state, &client, authorization).await
+}
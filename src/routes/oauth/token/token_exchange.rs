@@ -0,0 +1,350 @@
+use axum::http::StatusCode;
+use mongodb::bson;
+use nanoid::nanoid;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    database::{ client::ClientDocument, grant::GrantDocument, pairwise::PairwiseDocument },
+    env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, TOKEN_MAX_AGE },
+    routes::oauth::{ OAuthRoutesState, token::{ TokenPayload, TokenResponse } },
+    utils::{
+        jwt::{ ActorClaims, ClientClaims, KeyKind },
+        oauth::{ ACCESS_TOKEN_TYPE, SENSITIVE_SCOPES, parse_scope },
+        pairwise,
+        timestamp,
+    },
+};
+
+/// Who the subject token speaks for, and how far it reaches.
+struct Subject {
+    account_id: String,
+    scope: Option<Vec<String>>,
+    auth_time: bson::DateTime,
+    amr: Vec<String>,
+    act: Option<ActorClaims>,
+}
+
+/// Trades a user's token held by the caller for a narrower one bound to a delegation target.
+///
+/// The issued token never comes with a refresh token, it only lives as long as the call
+/// the caller is making on the user's behalf.
+pub async fn handler(
+    state: OAuthRoutesState,
+    client: ClientDocument,
+    payload: TokenPayload
+) -> OAuthResponseModel {
+    if !client.is_confidential() {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "unauthorized_client",
+            "Only confidential clients can exchange tokens."
+        );
+    }
+
+    let (Some(subject_token), Some(subject_token_type), Some(audience)) = (
+        payload.subject_token,
+        payload.subject_token_type,
+        payload.audience,
+    ) else {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "`subject_token`, `subject_token_type` and `audience` are required."
+        );
+    };
+
+    if
+        subject_token_type != ACCESS_TOKEN_TYPE ||
+        payload.requested_token_type.is_some_and(|kind| kind != ACCESS_TOKEN_TYPE)
+    {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_request",
+            "Only access tokens can be exchanged."
+        );
+    }
+
+    if !client.delegation_targets.contains(&audience) {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_target",
+            "The client is not allowed to act on this audience."
+        );
+    }
+
+    let target = match state.app.db.client.get(&audience).await {
+        Ok(Some(target)) => target,
+        Ok(None) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_target",
+                "The audience is not a registered client."
+            );
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive client {}: {}", audience, error);
+            return oauth::server_error();
+        }
+    };
+
+    // The audience has the last word on who may act for its users.
+    if !target.delegation_actors.contains(&client.client_id) {
+        return oauth::error(
+            StatusCode::BAD_REQUEST,
+            "invalid_target",
+            "The audience doesn't accept this client as an actor."
+        );
+    }
+
+    let subject = match resolve_subject(&state, &client, &subject_token).await {
+        Ok(Some(subject)) => subject,
+        Ok(None) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_grant",
+                "Invalid subject token."
+            );
+        }
+        Err(response) => {
+            return response;
+        }
+    };
+
+    // A first-party token reaches everything, so the caller has to say what it needs.
+    let scope = match (payload.scope, subject.scope) {
+        (Some(scope), subject_scope) => {
+            let scope = parse_scope(&scope);
+            let allowed = |scope: &String| {
+                target.scopes.contains(scope) &&
+                    match &subject_scope {
+                        Some(subject_scope) => subject_scope.contains(scope),
+                        None => !SENSITIVE_SCOPES.contains(&scope.as_str()),
+                    }
+            };
+            if scope.is_empty() || !scope.iter().all(allowed) {
+                return oauth::error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_scope",
+                    "The scope exceeds what can be delegated to the audience."
+                );
+            }
+            scope
+        }
+        (None, Some(subject_scope)) => {
+            let scope: Vec<String> = subject_scope
+                .into_iter()
+                .filter(|scope| target.scopes.contains(scope))
+                .collect();
+            if scope.is_empty() {
+                return oauth::error(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_scope",
+                    "The audience is not registered for any of the subject's scopes."
+                );
+            }
+            scope
+        }
+        (None, None) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_scope",
+                "`scope` is required when exchanging a first-party token."
+            );
+        }
+    };
+
+    // Only what the user agreed to share with the audience itself, an actor can't pass on more.
+    let consent = state.app.db.consent.get(&subject.account_id, &target.client_id).await;
+    match consent {
+        Ok(consent) if consented(&scope, consent.as_ref().map(|consent| &consent.scope[..])) => {}
+        Ok(_) => {
+            return oauth::error(
+                StatusCode::BAD_REQUEST,
+                "invalid_scope",
+                "The user hasn't consented to this scope for the audience."
+            );
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to retreive consent of {} for client {}: {}",
+                subject.account_id,
+                target.client_id,
+                error
+            );
+            return oauth::server_error();
+        }
+    }
+
+    let issued_at = timestamp::now();
+    let identifier = nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH);
+    let sub = pairwise::subject(&subject.account_id, &target.sector_identifier);
+
+    // The target now knows the account, so it shows up among the user's services.
+    let pairwise_document = PairwiseDocument {
+        account_id: subject.account_id.clone(),
+        client_id: target.client_id.clone(),
+        subject: sub.clone(),
+        issued_at: bson::DateTime::now(),
+    };
+
+    if let Err(error) = state.app.db.pairwise.record(&pairwise_document).await {
+        tracing::error!(
+            "Unable to record subject of {} for client {}: {}",
+            subject.account_id,
+            target.client_id,
+            error
+        );
+        return oauth::server_error();
+    }
+
+    let grant = GrantDocument {
+        identifier: identifier.clone(),
+        account_id: subject.account_id,
+        client_id: target.client_id.clone(),
+        scope: scope.clone(),
+        auth_time: subject.auth_time,
+        amr: subject.amr,
+        issued_at: bson::DateTime::from_millis(issued_at.as_millis() as i64),
+    };
+
+    match state.app.db.grant.clone().issue(&grant).await {
+        Ok(true) => {}
+        Ok(false) => {
+            tracing::error!("A nanoid collision was found.");
+            return oauth::server_error();
+        }
+        Err(error) => {
+            tracing::error!(
+                "Unable to issue a grant for {} to client {}: {}",
+                grant.account_id,
+                grant.client_id,
+                error
+            );
+            return oauth::server_error();
+        }
+    }
+
+    let scope = scope.join(" ");
+
+    let signed_access = state.app.jwt.generate_client(ClientClaims {
+        sub,
+        client_id: target.client_id,
+        scope: scope.clone(),
+        identifier,
+        kind: KeyKind::ClientAccess,
+        iat: issued_at,
+        exp: issued_at + *TOKEN_MAX_AGE,
+        act: Some(ActorClaims {
+            sub: client.client_id,
+            act: subject.act.map(Box::new),
+        }),
+    });
+
+    oauth::result(TokenResponse {
+        access_token: signed_access,
+        token_type: "Bearer",
+        expires_in: TOKEN_MAX_AGE.as_secs(),
+        refresh_token: None,
+        id_token: None,
+        issued_token_type: Some(ACCESS_TOKEN_TYPE),
+        scope,
+    })
+}
+
+/// Whether every scope is among those the user granted, nothing is without a consent.
+fn consented(scope: &[String], granted: Option<&[String]>) -> bool {
+    granted.is_some_and(|granted| scope.iter().all(|scope| granted.contains(scope)))
+}
+
+/// Accepts either the user's first-party token, or a token the caller itself received,
+/// which lets a chain of services keep delegating.
+async fn resolve_subject(
+    state: &OAuthRoutesState,
+    client: &ClientDocument,
+    subject_token: &str
+) -> Result<Option<Subject>, OAuthResponseModel> {
+    if let Some(claims) = state.app.jwt.verify(subject_token, KeyKind::Authentication) {
+        return match state.app.db.auth.clone().check_token(&claims).await {
+            Ok(true) => {
+                Ok(
+                    Some(Subject {
+                        account_id: claims.account_id,
+                        scope: None,
+                        auth_time: bson::DateTime::from_millis(
+                            claims.auth_time.as_millis() as i64
+                        ),
+                        amr: claims.amr,
+                        act: None,
+                    })
+                )
+            }
+            Ok(false) => Ok(None),
+            Err(error) => {
+                tracing::error!(
+                    "Failed to query database for token {}: {error}",
+                    claims.identifier
+                );
+                Err(oauth::server_error())
+            }
+        };
+    }
+
+    let Some(claims) = state.app.jwt.verify_client(subject_token, KeyKind::ClientAccess) else {
+        return Ok(None);
+    };
+
+    if claims.client_id != client.client_id {
+        return Ok(None);
+    }
+
+    match state.app.db.grant.clone().check(&claims).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Ok(None);
+        }
+        Err(error) => {
+            tracing::error!("Failed to query grant {}: {error}", claims.identifier);
+            return Err(oauth::server_error());
+        }
+    }
+
+    match state.app.db.grant.get(&claims.identifier).await {
+        Ok(Some(grant)) => {
+            Ok(
+                Some(Subject {
+                    account_id: grant.account_id,
+                    scope: Some(parse_scope(&claims.scope)),
+                    auth_time: grant.auth_time,
+                    amr: grant.amr,
+                    act: claims.act,
+                })
+            )
+        }
+        Ok(None) => Ok(None),
+        Err(error) => {
+            tracing::error!("Failed to query grant {}: {error}", claims.identifier);
+            Err(oauth::server_error())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scopes(scope: &str) -> Vec<String> {
+        parse_scope(scope)
+    }
+
+    #[test]
+    fn email_never_reaches_an_audience_without_consent() {
+        assert!(!consented(&scopes("openid email"), None));
+        assert!(!consented(&scopes("openid email"), Some(&scopes("openid"))));
+    }
+
+    #[test]
+    fn consented_scopes_pass() {
+        assert!(consented(&scopes("openid email"), Some(&scopes("email openid profile"))));
+        assert!(consented(&scopes("openid"), Some(&scopes("openid email"))));
+    }
+}
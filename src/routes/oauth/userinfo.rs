@@ -0,0 +1,86 @@
+use axum::{ extract::State, http::{ HeaderMap, StatusCode } };
+use serde::Serialize;
+
+use crate::{
+    base::oauth::{ self, OAuthResponseModel },
+    routes::oauth::OAuthRoutesState,
+    utils::{ jwt::KeyKind, oauth::bearer_token },
+};
+
+#[derive(Serialize)]
+pub struct UserInfoResponse {
+    sub: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    email_verified: Option<bool>,
+}
+
+pub async fn handler(
+    State(state): State<OAuthRoutesState>,
+    headers: HeaderMap
+) -> OAuthResponseModel {
+    let Some(access_token) = bearer_token(&headers) else {
+        return oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Bearer token required.");
+    };
+
+    let Some(claims) = state.app.jwt.verify_client(access_token, KeyKind::ClientAccess) else {
+        return oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Invalid access token.");
+    };
+
+    let scope: Vec<&str> = claims.scope.split(' ').collect();
+    if !scope.contains(&"openid") {
+        return oauth::error(
+            StatusCode::FORBIDDEN,
+            "insufficient_scope",
+            "The `openid` scope is required."
+        );
+    }
+
+    match state.app.db.grant.clone().check(&claims).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Invalid access token.");
+        }
+        Err(error) => {
+            tracing::error!("Failed to query grant {}: {error}", claims.identifier);
+            return oauth::server_error();
+        }
+    }
+
+    if !scope.contains(&"email") {
+        return oauth::result(UserInfoResponse {
+            sub: claims.sub,
+            email: None,
+            email_verified: None,
+        });
+    }
+
+    let grant = match state.app.db.grant.get(&claims.identifier).await {
+        Ok(Some(grant)) => grant,
+        Ok(None) => {
+            return oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Invalid access token.");
+        }
+        Err(error) => {
+            tracing::error!("Failed to query grant {}: {error}", claims.identifier);
+            return oauth::server_error();
+        }
+    };
+
+    let account = match state.app.db.account.get_active_from_id(&grant.account_id).await {
+        Ok(Some(account)) => account,
+        Ok(None) => {
+            return oauth::error(StatusCode::UNAUTHORIZED, "invalid_token", "Invalid access token.");
+        }
+        Err(error) => {
+            tracing::error!("Unable to retreive account for {}: {}", grant.account_id, error);
+            return oauth::server_error();
+        }
+    };
+
+    oauth::result(UserInfoResponse {
+        sub: claims.sub,
+        email: Some(account.email),
+        email_verified: Some(account.issued_at.is_some()),
+    })
+}
@@ -0,0 +1,8 @@
+use axum::{ Json, extract::State };
+use jsonwebtoken::jwk::JwkSet;
+
+use crate::routes::well_known::WellKnownRoutesState;
+
+pub async fn handler(State(state): State<WellKnownRoutesState>) -> Json<JwkSet> {
+    Json(state.app.jwt.jwks())
+}
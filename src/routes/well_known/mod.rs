@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use axum::{ Router, routing::get };
+
+use crate::AppState;
+
+mod openid_configuration;
+mod jwks;
+
+#[derive(Clone)]
+pub struct WellKnownRoutesState {
+    pub app: Arc<AppState>,
+}
+
+pub fn routes(app_state: Arc<AppState>) -> Router {
+    let state = WellKnownRoutesState {
+        app: app_state,
+    };
+
+    Router::new()
+        .route("/openid-configuration", get(openid_configuration::handler))
+        .route("/jwks.json", get(jwks::handler))
+        .with_state(state)
+}
@@ -0,0 +1,73 @@
+use axum::Json;
+use serde::Serialize;
+
+use crate::{
+    env::{ AUTHORIZE_PAGE, ISSUER },
+    utils::{ oauth::GRANT_TYPES, oidc::{ ACR_EMAIL, ACR_EXTERNAL, ACR_MFA, ACR_PASSWORD } },
+};
+
+#[derive(Serialize)]
+pub struct OpenIdConfiguration {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+    jwks_uri: String,
+    introspection_endpoint: String,
+    revocation_endpoint: String,
+    device_authorization_endpoint: String,
+    registration_endpoint: String,
+    scopes_supported: Vec<&'static str>,
+    response_types_supported: Vec<&'static str>,
+    grant_types_supported: Vec<&'static str>,
+    subject_types_supported: Vec<&'static str>,
+    id_token_signing_alg_values_supported: Vec<&'static str>,
+    token_endpoint_auth_methods_supported: Vec<&'static str>,
+    code_challenge_methods_supported: Vec<&'static str>,
+    acr_values_supported: Vec<&'static str>,
+    claims_supported: Vec<&'static str>,
+    backchannel_logout_supported: bool,
+    backchannel_logout_session_supported: bool,
+}
+
+pub async fn handler() -> Json<OpenIdConfiguration> {
+    Json(OpenIdConfiguration {
+        issuer: ISSUER.clone(),
+        authorization_endpoint: AUTHORIZE_PAGE.to_string(),
+        token_endpoint: format!("{}/oauth/token", *ISSUER),
+        userinfo_endpoint: format!("{}/oauth/userinfo", *ISSUER),
+        jwks_uri: format!("{}/.well-known/jwks.json", *ISSUER),
+        introspection_endpoint: format!("{}/oauth/introspect", *ISSUER),
+        revocation_endpoint: format!("{}/oauth/revoke", *ISSUER),
+        device_authorization_endpoint: format!("{}/oauth/device_authorization", *ISSUER),
+        registration_endpoint: format!("{}/oauth/register", *ISSUER),
+        scopes_supported: vec!["openid", "email"],
+        response_types_supported: vec!["code"],
+        grant_types_supported: GRANT_TYPES.to_vec(),
+        subject_types_supported: vec!["pairwise"],
+        id_token_signing_alg_values_supported: vec!["ES256"],
+        token_endpoint_auth_methods_supported: vec![
+            "client_secret_basic",
+            "client_secret_post",
+            "none"
+        ],
+        code_challenge_methods_supported: vec!["S256"],
+        acr_values_supported: vec![ACR_PASSWORD, ACR_EMAIL, ACR_EXTERNAL, ACR_MFA],
+        claims_supported: vec![
+            "iss",
+            "sub",
+            "aud",
+            "exp",
+            "iat",
+            "auth_time",
+            "nonce",
+            "acr",
+            "amr",
+            "email",
+            "email_verified"
+        ],
+        backchannel_logout_supported: true,
+        // Clients don't see Koii sessions, logout tokens only name the subject.
+        backchannel_logout_session_supported: false,
+    })
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use nanoid::nanoid;
+
+use crate::{
+    AppState,
+    env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, ISSUER },
+    utils::{ jwt::LogoutTokenClaims, timestamp },
+    workers::backchannel_logout::BackchannelLogoutRequest,
+};
+
+/// How long a logout token stays acceptable, long enough to outlive the delivery retries.
+const LOGOUT_TOKEN_MAX_AGE: Duration = Duration::from_secs(120);
+
+/// Tells every client that knows the account and registered a `backchannel_logout_uri`
+/// that the user logged out.
+///
+/// Delivery is left to the worker, a failure here never stops the user from logging out.
+pub async fn notify_clients(app: &AppState, account_id: &str) {
+    let subjects = match app.db.pairwise.list(account_id).await {
+        Ok(subjects) => subjects,
+        Err(error) => {
+            tracing::error!("Unable to list services for {}: {}", account_id, error);
+            return;
+        }
+    };
+
+    let issued_at = timestamp::now();
+    for subject in subjects {
+        let client = match app.db.client.get(&subject.client_id).await {
+            Ok(Some(client)) => client,
+            Ok(None) => {
+                continue;
+            }
+            Err(error) => {
+                tracing::error!("Unable to retreive client {}: {}", subject.client_id, error);
+                continue;
+            }
+        };
+
+        let Some(backchannel_logout_uri) = client.backchannel_logout_uri else {
+            continue;
+        };
+
+        let logout_token = app.jwt.generate_logout_token(LogoutTokenClaims {
+            iss: ISSUER.clone(),
+            sub: subject.subject.clone(),
+            aud: client.client_id.clone(),
+            iat: issued_at,
+            exp: issued_at + LOGOUT_TOKEN_MAX_AGE,
+            jti: nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH),
+        });
+
+        app.worker.backchannel_logout
+            .send_ignore(BackchannelLogoutRequest {
+                client_id: client.client_id,
+                backchannel_logout_uri,
+                subject: subject.subject,
+                logout_token,
+            }).await;
+    }
+}
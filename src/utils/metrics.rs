@@ -0,0 +1,72 @@
+use std::{ sync::OnceLock, thread, time::{ Duration, Instant } };
+
+use metrics_exporter_prometheus::{ Matcher, PrometheusBuilder, PrometheusHandle };
+
+/// Seconds, from a fast cached check up to the slowest argon2 hash under load.
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the global recorder, every `metrics` call before this is dropped.
+pub fn install() {
+    HANDLE.get_or_init(|| {
+        let handle = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), &LATENCY_BUCKETS)
+            .unwrap()
+            .install_recorder()
+            .unwrap();
+
+        // Histograms pile up samples until upkeep folds them in.
+        let upkeep = handle.clone();
+        thread::spawn(move || {
+            loop {
+                thread::sleep(Duration::from_secs(5));
+                upkeep.run_upkeep();
+            }
+        });
+
+        handle
+    });
+}
+
+/// Everything recorded so far, in Prometheus' text format.
+pub fn render() -> String {
+    HANDLE.get().map(|handle| handle.render()).unwrap_or_default()
+}
+
+pub fn request(method: String, route: String, status: u16, started: Instant) {
+    let labels = [("method", method), ("route", route), ("status", status.to_string())];
+
+    metrics::counter!("koii_http_requests_total", &labels).increment(1);
+    metrics::histogram!("koii_http_request_duration_seconds", &labels).record(
+        started.elapsed().as_secs_f64()
+    );
+}
+
+pub fn queue_depth(worker: &'static str, queued: usize) {
+    metrics::gauge!("koii_worker_queue_depth", "worker" => worker).set(queued as f64);
+}
+
+pub fn job(worker: &'static str, started: Instant) {
+    metrics::histogram!("koii_worker_job_duration_seconds", "worker" => worker).record(
+        started.elapsed().as_secs_f64()
+    );
+}
+
+/// Whether a token's state was in Redis, or had to be fetched from MongoDB.
+pub fn auth_cache(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    metrics::counter!("koii_auth_cache_total", "result" => result).increment(1);
+}
+
+/// `passed`, `rejected`, `bypassed` or `error` when Cloudflare couldn't be reached.
+pub fn turnstile(outcome: &'static str) {
+    metrics::counter!("koii_turnstile_total", "outcome" => outcome).increment(1);
+}
+
+pub fn login(method: &'static str, success: bool) {
+    let outcome = if success { "success" } else { "failure" };
+    metrics::counter!("koii_logins_total", "method" => method, "outcome" => outcome).increment(1);
+}
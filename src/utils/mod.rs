@@ -3,3 +3,15 @@ pub mod passkey;
 pub mod turnstile;
 pub mod totp;
 pub mod timestamp;
+pub mod pairwise;
+pub mod oauth;
+pub mod oidc;
+pub mod logout;
+pub mod upstream;
+pub mod sudo;
+pub mod tls;
+pub mod reload;
+pub mod shutdown;
+pub mod metrics;
+pub mod telemetry;
+pub mod redact;
@@ -0,0 +1,82 @@
+/// Authentication context when the user only proved their password.
+pub const ACR_PASSWORD: &str = "urn:koii:acr:password";
+
+/// Authentication context when the user signed in with a link or code sent to their email.
+pub const ACR_EMAIL: &str = "urn:koii:acr:email";
+
+/// Authentication context when an upstream identity provider vouched for the user.
+pub const ACR_EXTERNAL: &str = "urn:koii:acr:external";
+
+/// Authentication context when the user also passed a second factor through `MfaUpgrade`.
+pub const ACR_MFA: &str = "urn:koii:acr:mfa";
+
+/// Event type marking a back-channel logout token.
+pub const BACKCHANNEL_LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// How a session was started, recorded on its tokens.
+pub enum SignIn {
+    Password,
+    /// Magic link or code, both are one-time secrets sent by email.
+    Email,
+    /// Upstream identity provider, `ext` isn't registered in RFC 8176.
+    External,
+}
+
+impl SignIn {
+    pub fn amr(&self) -> Vec<String> {
+        let method = match self {
+            SignIn::Password => "pwd",
+            SignIn::Email => "otp",
+            SignIn::External => "ext",
+        };
+        vec![method.to_string()]
+    }
+}
+
+/// Authentication methods (RFC 8176) behind a session, with the second factor when the user
+/// just passed one.
+pub fn amr(session: &[String], mfa_upgraded: bool) -> Vec<String> {
+    let mut amr = session.to_vec();
+    if mfa_upgraded {
+        for method in ["otp", "mfa"] {
+            if !amr.iter().any(|existing| existing == method) {
+                amr.push(method.to_string());
+            }
+        }
+    }
+    amr
+}
+
+pub fn acr(amr: &[String]) -> &'static str {
+    let used = |method: &str| amr.iter().any(|existing| existing == method);
+
+    if used("mfa") {
+        ACR_MFA
+    } else if used("ext") {
+        ACR_EXTERNAL
+    } else if used("otp") {
+        ACR_EMAIL
+    } else {
+        ACR_PASSWORD
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_methods_decide_acr() {
+        assert_eq!(acr(&SignIn::Password.amr()), ACR_PASSWORD);
+        assert_eq!(acr(&SignIn::Email.amr()), ACR_EMAIL);
+        assert_eq!(acr(&SignIn::External.amr()), ACR_EXTERNAL);
+    }
+
+    #[test]
+    fn mfa_upgrade_adds_the_second_factor() {
+        assert_eq!(amr(&SignIn::Password.amr(), true), ["pwd", "otp", "mfa"]);
+        assert_eq!(amr(&SignIn::Email.amr(), true), ["otp", "mfa"]);
+        assert_eq!(amr(&SignIn::External.amr(), false), ["ext"]);
+        assert_eq!(acr(&amr(&SignIn::External.amr(), true)), ACR_MFA);
+    }
+}
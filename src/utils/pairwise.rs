@@ -0,0 +1,61 @@
+use base64::{ Engine, engine::general_purpose::URL_SAFE_NO_PAD };
+use hmac::{ Hmac, Mac };
+use sha2::Sha256;
+
+use crate::env::PAIRWISE_SECRET;
+
+/// Derives the subject that clients under `sector_identifier` know the account by.
+///
+/// Two sectors always see unrelated values for the same account, and without `PAIRWISE_SECRET`
+/// nobody can map a subject back to the account or to a subject of another sector.
+pub fn subject(account_id: &str, sector_identifier: &str) -> String {
+    keyed_subject(PAIRWISE_SECRET.as_bytes(), account_id, sector_identifier)
+}
+
+fn keyed_subject(secret: &[u8], account_id: &str, sector_identifier: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).unwrap();
+    mac.update(sector_identifier.as_bytes());
+    mac.update(b"\0");
+    mac.update(account_id.as_bytes());
+
+    URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keyed_subject;
+
+    #[test]
+    fn stable_for_the_same_sector() {
+        assert_eq!(
+            keyed_subject(b"secret", "account", "app.example"),
+            keyed_subject(b"secret", "account", "app.example")
+        );
+    }
+
+    #[test]
+    fn unrelated_across_sectors_and_secrets() {
+        let subject = keyed_subject(b"secret", "account", "app.example");
+
+        assert_ne!(subject, keyed_subject(b"secret", "account", "other.example"));
+        assert_ne!(subject, keyed_subject(b"other", "account", "app.example"));
+        assert_ne!(subject, keyed_subject(b"secret", "account2", "app.example"));
+        assert_ne!(subject, "account");
+    }
+
+    #[test]
+    fn separator_keeps_fields_apart() {
+        assert_ne!(
+            keyed_subject(b"secret", "b", "a"),
+            keyed_subject(b"secret", "", "ab")
+        );
+    }
+
+    #[test]
+    fn url_safe_without_padding() {
+        let subject = keyed_subject(b"secret", "account", "app.example");
+
+        assert_eq!(subject.len(), 43);
+        assert!(subject.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+}
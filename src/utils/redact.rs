@@ -0,0 +1,138 @@
+use std::{ fmt, net::IpAddr };
+
+use axum::http::Uri;
+use base64::{ Engine, engine::general_purpose::URL_SAFE_NO_PAD };
+use hmac::{ Hmac, Mac };
+use sha2::Sha256;
+
+use crate::env::LOG_SALT;
+
+/// Logs as `a***@example.com`, or hashed with `LOG_SALT` like the other wrappers here.
+pub struct Email<'a>(pub &'a str);
+
+/// Logs as `token(14)`, with its length only.
+pub struct Token<'a>(pub &'a str);
+
+/// Logs as `203.0.113.x` or `2001:db8:1::/48`, enough to tell networks apart.
+pub struct Ip(pub IpAddr);
+
+/// Logs the path, with every query value masked.
+pub struct Query<'a>(pub &'a Uri);
+
+impl fmt::Display for Email<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(hash) = salted("email", &self.0.trim().to_lowercase()) {
+            return f.write_str(&hash);
+        }
+
+        match self.0.split_once('@') {
+            Some((local, domain)) => {
+                let first = local.chars().next().map(String::from).unwrap_or_default();
+                write!(f, "{}***@{}", first, domain)
+            }
+            None => f.write_str("***"),
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match salted("token", self.0) {
+            Some(hash) => f.write_str(&hash),
+            None => write!(f, "token({})", self.0.len()),
+        }
+    }
+}
+
+impl fmt::Display for Ip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(hash) = salted("ip", &self.0.to_string()) {
+            return f.write_str(&hash);
+        }
+
+        match self.0 {
+            IpAddr::V4(ip) => {
+                let [a, b, c, _] = ip.octets();
+                write!(f, "{}.{}.{}.x", a, b, c)
+            }
+            IpAddr::V6(ip) => {
+                let [a, b, c, ..] = ip.segments();
+                write!(f, "{:x}:{:x}:{:x}::/48", a, b, c)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Query<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.path())?;
+
+        // Codes and tokens travel in query strings, only the keys are kept.
+        if let Some(query) = self.0.query() {
+            let keys: Vec<&str> = query
+                .split('&')
+                .map(|pair| pair.split_once('=').map_or(pair, |(key, _)| key))
+                .collect();
+            write!(f, "?{}=***", keys.join("=***&"))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `kind:` and the start of a salted HMAC, so lines about the same person can still be matched
+/// up without saying who. `None` without `LOG_SALT`, values are masked instead.
+fn salted(kind: &str, value: &str) -> Option<String> {
+    LOG_SALT.as_deref().map(|salt| keyed(salt, kind, value))
+}
+
+fn keyed(salt: &str, kind: &str, value: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).unwrap();
+    mac.update(kind.as_bytes());
+    mac.update(b"\0");
+    mac.update(value.as_bytes());
+
+    let hash = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+    format!("{}:{}", kind, &hash[..12])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `LOG_SALT` is never set for unit tests, values come out masked.
+
+    #[test]
+    fn masks_emails() {
+        assert_eq!(Email("alice@example.com").to_string(), "a***@example.com");
+        assert_eq!(Email("@example.com").to_string(), "***@example.com");
+        assert_eq!(Email("not an email").to_string(), "***");
+    }
+
+    #[test]
+    fn masks_ips_to_their_network() {
+        assert_eq!(Ip("203.0.113.42".parse().unwrap()).to_string(), "203.0.113.x");
+        assert_eq!(Ip("2001:db8:1:2::1".parse().unwrap()).to_string(), "2001:db8:1::/48");
+    }
+
+    #[test]
+    fn masks_query_values() {
+        let uri = Uri::from_static("/oauth/consent?code=secret&state=abc&flag");
+        assert_eq!(Query(&uri).to_string(), "/oauth/consent?code=***&state=***&flag=***");
+
+        let uri = Uri::from_static("/healthz");
+        assert_eq!(Query(&uri).to_string(), "/healthz");
+    }
+
+    #[test]
+    fn keyed_hashes_tell_kinds_and_salts_apart() {
+        let hash = keyed("salt", "email", "alice@example.com");
+        assert!(hash.starts_with("email:"));
+        assert_eq!(hash.len(), "email:".len() + 12);
+        assert_eq!(hash, keyed("salt", "email", "alice@example.com"));
+
+        assert_ne!(hash, keyed("pepper", "email", "alice@example.com"));
+        assert_ne!(keyed("salt", "ip", "x")[3..], keyed("salt", "token", "x")[6..]);
+        assert!(!hash.contains("alice"));
+    }
+}
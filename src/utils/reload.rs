@@ -0,0 +1,40 @@
+use std::sync::Arc;
+
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::signal::unix::{ SignalKind, signal };
+
+use crate::{ AppState, utils::tls };
+
+/// Reloads the TLS certificate, when serving over TLS, and JWT keys on every SIGHUP, so renewing
+/// them doesn't need a restart that would drop logins in flight.
+///
+/// Whatever fails to load is logged and the keys in use are kept.
+pub fn on_hangup(app: Arc<AppState>, tls_config: Option<RustlsConfig>) {
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(error) => {
+            tracing::error!("Unable to listen for SIGHUP, reloading is disabled: {}", error);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            tracing::info!("SIGHUP received, reloading...");
+
+            if let Some(tls_config) = &tls_config {
+                reload_tls(tls_config).await;
+            }
+            app.jwt.reload();
+        }
+    });
+}
+
+async fn reload_tls(tls_config: &RustlsConfig) {
+    match tls::server_config().await {
+        Ok(config) => tls_config.reload_from_config(config),
+        Err(error) => {
+            tracing::error!("Keeping the current TLS certificate, unable to reload: {}", error);
+        }
+    }
+}
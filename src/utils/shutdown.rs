@@ -0,0 +1,47 @@
+use std::{ net::SocketAddr, sync::OnceLock, time::Duration };
+
+use axum_server::Handle;
+use tokio::{ signal::unix::{ SignalKind, signal }, time::Instant };
+
+use crate::env::SHUTDOWN_TIMEOUT;
+
+/// When the signal came in, requests in flight and worker queues share one `SHUTDOWN_TIMEOUT`.
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// What's left of `SHUTDOWN_TIMEOUT` once the server stopped, for the workers to drain.
+pub fn remaining() -> Duration {
+    match STARTED.get() {
+        Some(started) => SHUTDOWN_TIMEOUT.saturating_sub(started.elapsed()),
+        None => *SHUTDOWN_TIMEOUT,
+    }
+}
+
+/// A handle that shuts the server down gracefully on SIGTERM or Ctrl+C.
+///
+/// New connections are refused right away, requests in flight get `SHUTDOWN_TIMEOUT` to finish
+/// and the workers whatever they left of it, see `remaining`.
+pub fn on_terminate() -> Handle<SocketAddr> {
+    let handle = Handle::new();
+    let shutdown = handle.clone();
+
+    tokio::spawn(async move {
+        let mut terminate = match signal(SignalKind::terminate()) {
+            Ok(terminate) => terminate,
+            Err(error) => {
+                tracing::error!("Unable to listen for SIGTERM: {}", error);
+                return;
+            }
+        };
+
+        tokio::select! {
+            _ = terminate.recv() => {}
+            _ = tokio::signal::ctrl_c() => {}
+        }
+
+        STARTED.get_or_init(Instant::now);
+        tracing::info!("Shutting down, waiting for requests in flight...");
+        shutdown.graceful_shutdown(Some(*SHUTDOWN_TIMEOUT));
+    });
+
+    handle
+}
@@ -0,0 +1,88 @@
+use mongodb::bson;
+use nanoid::nanoid;
+
+use crate::{
+    AppState,
+    database::{ mfa_upgrade::MfaUpgradeDocument, sudo::SudoDocument },
+    env::{ ACCOUNT_TOKEN_IDENTIFIER_LENGTH, SUDO_MAX_AGE },
+    utils::{ jwt::{ KeyClaims, KeyKind }, timestamp },
+};
+
+/// Elevates the account once the user proved it's them again, whichever factor they used.
+///
+/// `None` on a nanoid collision.
+pub async fn grant(
+    app: &AppState,
+    account_id: &str
+) -> Result<Option<String>, mongodb::error::Error> {
+    let issued_at = timestamp::now();
+    let document = SudoDocument {
+        account_id: account_id.to_string(),
+        identifier: nanoid!(*ACCOUNT_TOKEN_IDENTIFIER_LENGTH),
+        issued_at: bson::DateTime::from_millis(issued_at.as_millis() as i64),
+    };
+
+    if !app.db.sudo.elevate(&document).await? {
+        return Ok(None);
+    }
+
+    Ok(
+        Some(
+            app.jwt.generate(KeyClaims {
+                account_id: document.account_id,
+                identifier: document.identifier,
+                kind: KeyKind::Sudo,
+                iat: issued_at,
+                exp: issued_at + *SUDO_MAX_AGE,
+                auth_time: issued_at,
+                amr: Vec::new(),
+            })
+        )
+    )
+}
+
+/// Whether `sudo` is a live `Sudo` token for the account, for routes that change how the
+/// account is signed in to.
+pub async fn authorize(
+    app: &AppState,
+    account_id: &str,
+    sudo: &str
+) -> Result<bool, mongodb::error::Error> {
+    let Some(claims) = app.jwt.verify(sudo, KeyKind::Sudo) else {
+        return Ok(false);
+    };
+
+    if claims.account_id != account_id {
+        return Ok(false);
+    }
+
+    app.db.sudo.authorize(account_id, claims.identifier).await
+}
+
+/// Spends an `MfaUpgrade` token of the account, `None` if it isn't one or was spent before.
+///
+/// One second factor elevates one request, a token caught on the way can't be replayed.
+pub async fn redeem_mfa_upgrade(
+    app: &AppState,
+    account_id: &str,
+    mfa_upgrade: &str
+) -> Result<Option<KeyClaims>, mongodb::error::Error> {
+    let Some(claims) = app.jwt.verify(mfa_upgrade, KeyKind::MfaUpgrade) else {
+        return Ok(None);
+    };
+
+    if claims.account_id != account_id {
+        return Ok(None);
+    }
+
+    let document = MfaUpgradeDocument {
+        account_id: claims.account_id.clone(),
+        identifier: claims.identifier.clone(),
+        issued_at: bson::DateTime::from_millis(claims.iat.as_millis() as i64),
+    };
+
+    match app.db.mfa_upgrade.consume(&document).await? {
+        true => Ok(Some(claims)),
+        false => Ok(None),
+    }
+}
@@ -0,0 +1,82 @@
+use std::{ collections::HashMap, sync::{ Arc, Mutex, OnceLock } };
+
+use mongodb::event::{ EventHandler, command::CommandEvent };
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{ Resource, trace::{ SdkTracer, SdkTracerProvider } };
+use tracing::{ Span, Subscriber };
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::env::OTLP_ENDPOINT;
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Exports spans over OTLP/HTTP when `OTEL_EXPORTER_OTLP_ENDPOINT` is set, `None` otherwise.
+///
+/// The other `OTEL_*` variables, headers and timeouts included, are read as the spec says.
+pub fn layer<S>() -> Option<OpenTelemetryLayer<S, SdkTracer>>
+    where S: Subscriber + for<'span> LookupSpan<'span>
+{
+    OTLP_ENDPOINT.as_ref()?;
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .build()
+        .expect("Unable to set up the OTLP exporter.");
+
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or("koii".to_string());
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name).build())
+        .build();
+
+    let tracer = provider.tracer("koii");
+    PROVIDER.set(provider).ok();
+
+    Some(tracing_opentelemetry::layer().with_tracer(tracer))
+}
+
+/// Sends the spans still batched, call last.
+pub fn shutdown() {
+    if let Some(provider) = PROVIDER.get() && let Err(error) = provider.shutdown() {
+        tracing::error!("Unable to flush the last spans: {}", error);
+    }
+}
+
+/// Opens a span for every MongoDB command, under the span that ran it.
+///
+/// Commands are only named, their documents hold account data.
+pub fn mongo_command_handler() -> Option<EventHandler<CommandEvent>> {
+    PROVIDER.get()?;
+
+    let spans: Arc<Mutex<HashMap<i32, Span>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    Some(
+        EventHandler::callback(move |event| {
+            match event {
+                CommandEvent::Started(event) => {
+                    let span = tracing::info_span!(
+                        "mongodb",
+                        otel.name = event.command_name,
+                        db.system = "mongodb",
+                        db.name = event.db,
+                    );
+                    spans.lock().unwrap().insert(event.request_id, span);
+                }
+                // The span ends when dropped.
+                CommandEvent::Succeeded(event) => {
+                    spans.lock().unwrap().remove(&event.request_id);
+                }
+                CommandEvent::Failed(event) => {
+                    if let Some(span) = spans.lock().unwrap().remove(&event.request_id) {
+                        span.in_scope(|| {
+                            tracing::warn!("MongoDB command failed: {}", event.failure)
+                        });
+                    }
+                }
+                _ => {}
+            }
+        })
+    )
+}
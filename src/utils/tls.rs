@@ -0,0 +1,205 @@
+use std::{ io, sync::Arc };
+
+use base64::{ Engine, engine::general_purpose::URL_SAFE_NO_PAD };
+
+use rustls::{
+    RootCertStore,
+    ServerConfig,
+    pki_types::{ CertificateDer, PrivateKeyDer, pem::PemObject },
+    server::WebPkiClientVerifier,
+};
+use sha2::{ Digest, Sha256 };
+
+use crate::env::{ CLIENT_CA_BUNDLE, SSL_CERT, SSL_KEY };
+
+/// Builds the TLS config for the secure context from `SSL_CERT` and `SSL_KEY`.
+///
+/// With `CLIENT_CA_BUNDLE` set, connections must present a certificate signed by it, so only
+/// the edge (Cloudflare's authenticated origin pulls) can reach Koii directly.
+pub async fn server_config() -> io::Result<Arc<ServerConfig>> {
+    let cert = tokio::fs::read(&*SSL_CERT).await?;
+    let key = tokio::fs::read(&*SSL_KEY).await?;
+    let bundle = match &*CLIENT_CA_BUNDLE {
+        Some(bundle) => Some(tokio::fs::read(bundle).await?),
+        None => None,
+    };
+
+    build(&cert, &key, bundle.as_deref())
+}
+
+/// `server_config` from PEM contents instead of files.
+fn build(cert: &[u8], key: &[u8], bundle: Option<&[u8]>) -> io::Result<Arc<ServerConfig>> {
+    let cert_chain = CertificateDer::pem_slice_iter(cert)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io::Error::other)?;
+    let key = PrivateKeyDer::from_pem_slice(key).map_err(io::Error::other)?;
+
+    let Some(leaf) = cert_chain.first() else {
+        return Err(io::Error::other("no certificate in SSL_CERT"));
+    };
+    let fingerprint = URL_SAFE_NO_PAD.encode(Sha256::digest(leaf));
+
+    let builder = match bundle {
+        Some(bundle) => {
+            let mut roots = RootCertStore::empty();
+            for ca in CertificateDer::pem_slice_iter(bundle) {
+                roots.add(ca.map_err(io::Error::other)?).map_err(io::Error::other)?;
+            }
+
+            let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+                .build()
+                .map_err(io::Error::other)?;
+
+            ServerConfig::builder().with_client_cert_verifier(verifier)
+        }
+        None => ServerConfig::builder().with_no_client_auth(),
+    };
+
+    let mut config = builder.with_single_cert(cert_chain, key).map_err(io::Error::other)?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+    tracing::info!("TLS certificate loaded (SHA-256: {}).", fingerprint);
+
+    Ok(Arc::new(config))
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{
+        BasicConstraints,
+        CertificateParams,
+        CertifiedIssuer,
+        ExtendedKeyUsagePurpose,
+        IsCa,
+        KeyPair,
+        KeyUsagePurpose,
+    };
+    use rustls::{ ClientConfig, ClientConnection, ServerConnection, pki_types::ServerName };
+
+    use super::*;
+
+    struct Ca {
+        issuer: CertifiedIssuer<'static, KeyPair>,
+    }
+
+    impl Ca {
+        fn new(name: &str) -> Self {
+            let mut params = CertificateParams::new(Vec::new()).unwrap();
+            params.distinguished_name.push(rcgen::DnType::CommonName, name);
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            params.key_usages = vec![
+                KeyUsagePurpose::KeyCertSign,
+                KeyUsagePurpose::DigitalSignature
+            ];
+
+            let key = KeyPair::generate().unwrap();
+            Ca { issuer: CertifiedIssuer::self_signed(params, key).unwrap() }
+        }
+
+        /// PEM certificate and key for `name`, signed by this CA.
+        fn sign(&self, name: &str, usage: ExtendedKeyUsagePurpose) -> (String, String) {
+            let key = KeyPair::generate().unwrap();
+            let mut params = CertificateParams::new(vec![name.to_string()]).unwrap();
+            params.extended_key_usages = vec![usage];
+
+            let cert = params.signed_by(&key, &self.issuer).unwrap();
+            (cert.pem(), key.serialize_pem())
+        }
+    }
+
+    /// Runs a handshake in memory, the error of whichever side gave up.
+    fn handshake(server: Arc<ServerConfig>, client: ClientConfig) -> Result<(), rustls::Error> {
+        let name = ServerName::try_from("localhost").unwrap();
+        let mut client = ClientConnection::new(Arc::new(client), name)?;
+        let mut server = ServerConnection::new(server)?;
+
+        for _ in 0..10 {
+            let mut bytes = Vec::new();
+            while client.wants_write() {
+                client.write_tls(&mut bytes).unwrap();
+            }
+            server.read_tls(&mut &bytes[..]).unwrap();
+            server.process_new_packets()?;
+
+            let mut bytes = Vec::new();
+            while server.wants_write() {
+                server.write_tls(&mut bytes).unwrap();
+            }
+            client.read_tls(&mut &bytes[..]).unwrap();
+            client.process_new_packets()?;
+
+            if !client.is_handshaking() && !server.is_handshaking() {
+                return Ok(());
+            }
+        }
+
+        panic!("The handshake never finished.");
+    }
+
+    struct Setup {
+        ca: Ca,
+        server: Arc<ServerConfig>,
+    }
+
+    fn setup(require_client_certificate: bool) -> Setup {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+
+        let ca = Ca::new("Koii test CA");
+        let (cert, key) = ca.sign("localhost", ExtendedKeyUsagePurpose::ServerAuth);
+        let bundle = ca.issuer.pem();
+        let bundle = require_client_certificate.then_some(bundle.as_bytes());
+
+        let server = build(cert.as_bytes(), key.as_bytes(), bundle).unwrap();
+        Setup { ca, server }
+    }
+
+    fn client(ca: &Ca, certificate: Option<(String, String)>) -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.issuer.der().clone()).unwrap();
+        let builder = ClientConfig::builder().with_root_certificates(roots);
+
+        match certificate {
+            Some((cert, key)) => {
+                let chain = CertificateDer::pem_slice_iter(cert.as_bytes())
+                    .collect::<Result<Vec<_>, _>>()
+                    .unwrap();
+                let key = PrivateKeyDer::from_pem_slice(key.as_bytes()).unwrap();
+                builder.with_client_auth_cert(chain, key).unwrap()
+            }
+            None => builder.with_no_client_auth(),
+        }
+    }
+
+    #[test]
+    fn anyone_connects_without_a_bundle() {
+        let setup = setup(false);
+        assert!(handshake(setup.server, client(&setup.ca, None)).is_ok());
+    }
+
+    #[test]
+    fn accepts_clients_signed_by_the_bundle() {
+        let setup = setup(true);
+        let certificate = setup.ca.sign("edge", ExtendedKeyUsagePurpose::ClientAuth);
+        assert!(handshake(setup.server, client(&setup.ca, Some(certificate))).is_ok());
+    }
+
+    #[test]
+    fn refuses_clients_without_a_certificate() {
+        let setup = setup(true);
+        let result = handshake(setup.server, client(&setup.ca, None));
+        assert!(matches!(result, Err(rustls::Error::NoCertificatesPresented)));
+    }
+
+    #[test]
+    fn refuses_clients_signed_by_another_ca() {
+        let setup = setup(true);
+        let certificate = Ca::new("Someone else").sign("edge", ExtendedKeyUsagePurpose::ClientAuth);
+        let result = handshake(setup.server, client(&setup.ca, Some(certificate)));
+        assert!(
+            matches!(
+                result,
+                Err(rustls::Error::InvalidCertificate(rustls::CertificateError::UnknownIssuer))
+            )
+        );
+    }
+}
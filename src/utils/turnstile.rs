@@ -2,7 +2,7 @@ use std::time::Duration;
 
 use serde::Deserialize;
 
-use crate::env::TURNSTILE_SECRET;
+use crate::{ env::TURNSTILE_SECRET, utils::metrics };
 
 #[derive(Deserialize)]
 pub struct TurnstileResult {
@@ -37,9 +37,11 @@ impl Turnstile {
     pub async fn verify(&self, turnstile_token: String, bypass: bool) -> Result<bool, ()> {
         if bypass {
             tracing::warn!("Bypass method for Turnstile was called.");
+            metrics::turnstile("bypassed");
             return Ok(true);
         }
         if turnstile_token.len() > 2048 {
+            metrics::turnstile("rejected");
             return Ok(false);
         }
 
@@ -84,8 +86,14 @@ impl Turnstile {
         };
 
         return match response {
-            Some(response) => Ok(response.success),
-            None => Err(()),
+            Some(response) => {
+                metrics::turnstile(if response.success { "passed" } else { "rejected" });
+                Ok(response.success)
+            }
+            None => {
+                metrics::turnstile("error");
+                Err(())
+            }
         };
     }
 }
@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+use crate::utils::upstream::{ IdentityProvider, ProviderConfig, UpstreamError, UpstreamIdentity };
+
+/// GitHub OAuth apps, GitHub has no OpenID Connect for users so the identity comes from its API.
+pub struct GithubProvider {
+    config: ProviderConfig,
+    authorize_url: String,
+    token_url: String,
+    api_url: String,
+    scope: String,
+}
+
+#[derive(Deserialize)]
+struct GithubUser {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+impl GithubProvider {
+    pub fn new(config: ProviderConfig) -> Self {
+        GithubProvider {
+            authorize_url: config.setting(
+                "AUTHORIZE_URL",
+                "https://github.com/login/oauth/authorize"
+            ),
+            token_url: config.setting("TOKEN_URL", "https://github.com/login/oauth/access_token"),
+            api_url: config
+                .setting("API_URL", "https://api.github.com")
+                .trim_end_matches('/')
+                .to_string(),
+            scope: config.setting("SCOPE", "read:user user:email"),
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for GithubProvider {
+    async fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+        _nonce: &str,
+        code_challenge: &str
+    ) -> Result<Url, UpstreamError> {
+        self.config.authorization_url(
+            &self.authorize_url,
+            &self.scope,
+            redirect_uri,
+            state,
+            code_challenge
+        )
+    }
+
+    async fn identify(
+        &self,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str,
+        _nonce: &str
+    ) -> Result<UpstreamIdentity, UpstreamError> {
+        let tokens = self.config.exchange_code(
+            &self.token_url,
+            redirect_uri,
+            code,
+            code_verifier
+        ).await?;
+        let access_token = tokens.access_token.unwrap_or_default();
+
+        let user = self.config.http_client
+            .get(format!("{}/user", self.api_url))
+            .bearer_auth(&access_token)
+            .send().await?
+            .error_for_status()?
+            .json::<GithubUser>().await?;
+
+        // The profile email is whatever the user typed in, only this list says what's verified.
+        let emails = self.config.http_client
+            .get(format!("{}/user/emails", self.api_url))
+            .bearer_auth(&access_token)
+            .send().await?
+            .error_for_status()?
+            .json::<Vec<GithubEmail>>().await?;
+
+        let primary = emails.into_iter().find(|email| email.primary);
+
+        Ok(UpstreamIdentity {
+            subject: user.id.to_string(),
+            email_verified: primary.as_ref().is_some_and(|email| email.verified),
+            email: primary.map(|email| email.email),
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use url::Url;
+
+use crate::utils::upstream::{ IdentityProvider, ProviderConfig, UpstreamError, UpstreamIdentity };
+
+/// GitLab OAuth applications, on gitlab.com or a self-hosted instance at
+/// `UPSTREAM_{NAME}_BASE_URL`.
+pub struct GitlabProvider {
+    config: ProviderConfig,
+    base_url: String,
+    scope: String,
+}
+
+#[derive(Deserialize)]
+struct GitlabUser {
+    id: u64,
+    email: Option<String>,
+    /// Only set once the primary email was confirmed.
+    confirmed_at: Option<String>,
+}
+
+impl GitlabProvider {
+    pub fn new(config: ProviderConfig) -> Self {
+        GitlabProvider {
+            base_url: config
+                .setting("BASE_URL", "https://gitlab.com")
+                .trim_end_matches('/')
+                .to_string(),
+            scope: config.setting("SCOPE", "read_user"),
+            config,
+        }
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for GitlabProvider {
+    async fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+        _nonce: &str,
+        code_challenge: &str
+    ) -> Result<Url, UpstreamError> {
+        self.config.authorization_url(
+            &format!("{}/oauth/authorize", self.base_url),
+            &self.scope,
+            redirect_uri,
+            state,
+            code_challenge
+        )
+    }
+
+    async fn identify(
+        &self,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str,
+        _nonce: &str
+    ) -> Result<UpstreamIdentity, UpstreamError> {
+        let tokens = self.config.exchange_code(
+            &format!("{}/oauth/token", self.base_url),
+            redirect_uri,
+            code,
+            code_verifier
+        ).await?;
+
+        let user = self.config.http_client
+            .get(format!("{}/api/v4/user", self.base_url))
+            .bearer_auth(tokens.access_token.unwrap_or_default())
+            .send().await?
+            .error_for_status()?
+            .json::<GitlabUser>().await?;
+
+        Ok(UpstreamIdentity {
+            subject: user.id.to_string(),
+            email_verified: user.email.is_some() && user.confirmed_at.is_some(),
+            email: user.email,
+        })
+    }
+}
@@ -0,0 +1,203 @@
+use std::{ collections::BTreeMap, time::Duration };
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+use crate::env::{ EXTERNAL_CALLBACK_PAGE, UPSTREAM_PROVIDERS, upstream_value };
+
+mod oidc;
+mod github;
+mod gitlab;
+
+/// Who the user is at an upstream provider.
+pub struct UpstreamIdentity {
+    /// Stable ID at the provider, accounts are only ever matched on this.
+    pub subject: String,
+    pub email: Option<String>,
+    /// Only `true` when the provider vouches for the email itself.
+    pub email_verified: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum UpstreamError {
+    #[error("Bad provider response")] Http(#[from] reqwest::Error),
+    #[error("Bad provider URL")] Url(#[from] url::ParseError),
+    #[error("Bad ID token")] IdToken(#[from] jsonwebtoken::errors::Error),
+    #[error("Provider refused: {0}")] Refused(String),
+}
+
+/// An upstream identity provider users can sign in with.
+///
+/// Every provider is driven the same way: send the user to `authorization_url`, then trade
+/// the code they come back with through `identify`.
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    /// Where to send the user, with the `state`, `nonce` and PKCE `S256` challenge included.
+    async fn authorization_url(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str
+    ) -> Result<Url, UpstreamError>;
+
+    /// Trades the authorization code for the identity behind it.
+    async fn identify(
+        &self,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str
+    ) -> Result<UpstreamIdentity, UpstreamError>;
+}
+
+/// Every provider configured in `UPSTREAM_PROVIDERS`.
+pub struct Upstream {
+    providers: BTreeMap<String, Box<dyn IdentityProvider>>,
+}
+
+impl Upstream {
+    /// Will panic if a configured provider is missing its credentials.
+    pub fn load() -> Self {
+        let mut providers: BTreeMap<String, Box<dyn IdentityProvider>> = BTreeMap::new();
+
+        for name in UPSTREAM_PROVIDERS.iter() {
+            let kind = upstream_value(name, "KIND").unwrap_or_else(|| {
+                match name.as_str() {
+                    "github" | "gitlab" | "microsoft" => name.clone(),
+                    _ => "oidc".to_string(),
+                }
+            });
+            let config = ProviderConfig::new(name);
+
+            let provider: Box<dyn IdentityProvider> = match kind.as_str() {
+                "oidc" => Box::new(oidc::OidcProvider::new(config)),
+                "microsoft" => Box::new(oidc::OidcProvider::microsoft(config)),
+                "github" => Box::new(github::GithubProvider::new(config)),
+                "gitlab" => Box::new(gitlab::GitlabProvider::new(config)),
+                _ => {
+                    panic!(
+                        "UPSTREAM_{}_KIND must be oidc, microsoft, github or gitlab.",
+                        name.to_uppercase()
+                    )
+                }
+            };
+
+            tracing::info!("Upstream provider `{}` enabled ({}).", name, kind);
+            providers.insert(name.clone(), provider);
+        }
+
+        Upstream { providers }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn IdentityProvider> {
+        self.providers.get(name).map(|provider| provider.as_ref())
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.providers.keys().cloned().collect()
+    }
+}
+
+/// Where a provider sends the user back to, registered as is at the provider.
+pub fn redirect_uri(provider: &str) -> String {
+    format!("{}/{}", EXTERNAL_CALLBACK_PAGE.as_str().trim_end_matches('/'), provider)
+}
+
+/// Settings shared by every kind of provider.
+struct ProviderConfig {
+    name: String,
+    client_id: String,
+    client_secret: String,
+    http_client: reqwest::Client,
+}
+
+impl ProviderConfig {
+    fn new(name: &str) -> Self {
+        let required = |key: &str| {
+            upstream_value(name, key).unwrap_or_else(|| {
+                panic!("UPSTREAM_{}_{key} must be set in .env file.", name.to_uppercase())
+            })
+        };
+
+        ProviderConfig {
+            name: name.to_string(),
+            client_id: required("CLIENT_ID"),
+            client_secret: required("CLIENT_SECRET"),
+            http_client: reqwest::Client
+                ::builder()
+                .timeout(Duration::from_secs(10))
+                .user_agent("Koii")
+                .build()
+                .unwrap(),
+        }
+    }
+
+    /// A configured value such as an endpoint, or what the public provider uses.
+    fn setting(&self, key: &str, default: &str) -> String {
+        upstream_value(&self.name, key).unwrap_or_else(|| default.to_string())
+    }
+
+    /// Builds the authorization request every provider kind shares.
+    fn authorization_url(
+        &self,
+        authorization_endpoint: &str,
+        scope: &str,
+        redirect_uri: &str,
+        state: &str,
+        code_challenge: &str
+    ) -> Result<Url, UpstreamError> {
+        let mut url = Url::parse(authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", scope)
+            .append_pair("state", state)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(url)
+    }
+
+    /// RFC 6749 code exchange, with the PKCE verifier.
+    async fn exchange_code(
+        &self,
+        token_endpoint: &str,
+        redirect_uri: &str,
+        code: &str,
+        code_verifier: &str
+    ) -> Result<UpstreamTokens, UpstreamError> {
+        let response = self.http_client
+            .post(token_endpoint)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .form(
+                &[
+                    ("grant_type", "authorization_code"),
+                    ("code", code),
+                    ("redirect_uri", redirect_uri),
+                    ("code_verifier", code_verifier),
+                    ("client_id", &self.client_id),
+                    ("client_secret", &self.client_secret),
+                ]
+            )
+            .send().await?;
+
+        // GitHub answers errors with a 200, only the body tells.
+        let tokens = response.json::<UpstreamTokens>().await?;
+        match (&tokens.access_token, &tokens.error) {
+            (Some(_), None) => Ok(tokens),
+            (_, Some(error)) => Err(UpstreamError::Refused(error.clone())),
+            (None, None) => Err(UpstreamError::Refused("no access token".to_string())),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UpstreamTokens {
+    access_token: Option<String>,
+    id_token: Option<String>,
+    error: Option<String>,
+}
@@ -0,0 +1,114 @@
+use std::time::{ Duration, Instant };
+
+use mongodb::bson;
+use tracing::Instrument;
+
+use crate::{
+    database::dead_letter::{ DeadLetterDocument, DeadLetterOperations },
+    utils::metrics,
+    workers::Job,
+};
+
+/// Attempts per logout token before it goes to the dead letters.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Wait before the first retry, doubled after every failure.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Clients have to answer quickly, OIDC expects the logout to happen in the request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct BackchannelLogoutRequest {
+    pub client_id: String,
+    pub backchannel_logout_uri: String,
+    /// Kept in the dead letters, the token itself is useless by the time anyone reads them.
+    pub subject: String,
+    pub logout_token: String,
+}
+
+// Deliveries wait on the network, so the "threads" here are tasks on the runtime.
+pub fn launch(
+    rx: kanal::AsyncReceiver<Job<BackchannelLogoutRequest, ()>>,
+    threads: usize,
+    dead_letter: DeadLetterOperations
+) {
+    // A redirect would send the token wherever the client points it, past the URI checks.
+    let http_client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+
+    for _ in 0..threads {
+        tokio::spawn(worker(rx.clone(), http_client.clone(), dead_letter.clone()));
+    }
+}
+
+async fn worker(
+    rx: kanal::AsyncReceiver<Job<BackchannelLogoutRequest, ()>>,
+    http_client: reqwest::Client,
+    dead_letter: DeadLetterOperations
+) {
+    while let Ok(Job { request, span, .. }) = rx.recv().await {
+        let started = Instant::now();
+        deliver(&http_client, &dead_letter, request)
+            .instrument(tracing::info_span!(parent: &span, "backchannel_logout")).await;
+        metrics::job("backchannel_logout", started);
+    }
+}
+
+async fn deliver(
+    http_client: &reqwest::Client,
+    dead_letter: &DeadLetterOperations,
+    request: BackchannelLogoutRequest
+) {
+    let mut last_error = String::new();
+    let mut delay = RETRY_DELAY;
+
+    for attempt in 1..=MAX_ATTEMPTS {
+        let response = http_client
+            .post(&request.backchannel_logout_uri)
+            .form(&[("logout_token", &request.logout_token)])
+            .send().await;
+
+        match response {
+            Ok(response) if response.status().is_success() => {
+                return;
+            }
+            Ok(response) => {
+                last_error = format!("Responded with {}", response.status());
+            }
+            Err(error) => {
+                last_error = error.to_string();
+            }
+        }
+
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+
+    tracing::warn!(
+        "Gave up on back-channel logout to client {}: {}",
+        request.client_id,
+        last_error
+    );
+
+    let document = DeadLetterDocument {
+        worker: "backchannel_logout".to_string(),
+        target: request.backchannel_logout_uri,
+        payload: bson::doc! { "client_id": &request.client_id, "sub": request.subject },
+        attempts: MAX_ATTEMPTS,
+        last_error,
+        failed_at: bson::DateTime::now(),
+    };
+
+    if let Err(error) = dead_letter.add(&document).await {
+        tracing::error!(
+            "Unable to record dead letter for client {}: {}",
+            request.client_id,
+            error
+        );
+    }
+}
@@ -0,0 +1,155 @@
+use std::{ collections::HashMap, thread, time::Instant };
+use resend_rs::{ Resend, types::{ CreateEmailBaseOptions, EmailTemplate } };
+
+use crate::{
+    env::{ EMAIL_BATCHING_WINDOW, ORIGIN_DOMAIN, RESEND_TOKEN },
+    utils::metrics,
+    workers::Job,
+};
+
+/// Every email Koii sends, each kind has its own Resend template.
+pub enum EmailRequest {
+    /// Link to verify a new account.
+    Verify {
+        email: String,
+        verify_code: String,
+    },
+
+    /// Link and code to log in without a password.
+    Login {
+        email: String,
+        login_token: String,
+        login_code: String,
+    },
+
+    /// Heads up that someone kept entering wrong passwords and the account got locked.
+    Lockout {
+        email: String,
+    },
+
+    /// Code to elevate a session to sudo, for accounts without MFA.
+    Sudo {
+        email: String,
+        sudo_code: String,
+    },
+}
+
+// The oneshot param is required by design for each services, but we don't use it.
+pub fn launch(
+    rx: kanal::AsyncReceiver<Job<EmailRequest, ()>>,
+    threads: usize
+) {
+    if threads > 1 {
+        tracing::warn!("But sire, there can only be 1 email worker.");
+    }
+
+    let rx = rx.to_sync();
+    thread::spawn(|| { worker(rx) });
+}
+
+fn worker(rx: kanal::Receiver<Job<EmailRequest, ()>>) {
+    // DO NOT MOVE THIS UP TO THE LAUNCHER FUNCTION.
+    // Resend uses `reqwest` under the hood.
+    // And if it's defined as blocking, it can't be initialized inside of tokio context.
+    let resend = Resend::new(&RESEND_TOKEN);
+
+    let mut requests = Vec::with_capacity(100);
+
+    loop {
+        // Checked before the queue, so emails sent right before the shutdown still go out.
+        let closed = rx.is_disconnected();
+        let waiting = rx.len();
+        if waiting > 1 {
+            let started = Instant::now();
+            rx.drain_into(&mut requests).unwrap();
+
+            // A batch serves many traces, it links to each instead of picking a parent.
+            let span = tracing::info_span!(parent: None, "email_batch");
+            let batch: Vec<CreateEmailBaseOptions> = requests
+                .drain(..)
+                .map(|job| {
+                    span.follows_from(&job.span);
+                    create_base(&job.request)
+                })
+                .collect();
+            let _span = span.entered();
+
+            if let Err(error) = resend.batch.send(batch) {
+                tracing::error!("Can't send email batch to Resend API: {error}");
+            }
+            metrics::job("email", started);
+
+            continue;
+        }
+
+        if waiting == 1 {
+            let started = Instant::now();
+            let job = rx.recv().unwrap();
+            let _span = tracing::info_span!(parent: &job.span, "email").entered();
+            if let Err(error) = resend.emails.send(create_base(&job.request)) {
+                tracing::error!("Can't send email batch to Resend API: {error}");
+            }
+            metrics::job("email", started);
+        }
+
+        // Shutting down, nothing else is coming to batch with.
+        if closed {
+            if waiting == 0 {
+                return;
+            }
+            continue;
+        }
+
+        thread::sleep(*EMAIL_BATCHING_WINDOW);
+    }
+}
+
+fn create_base(request: &EmailRequest) -> CreateEmailBaseOptions {
+    let origin = ORIGIN_DOMAIN.clone();
+    let domain = origin.domain().unwrap();
+    let mut variables = HashMap::new();
+
+    let (email, subject, template) = match request {
+        EmailRequest::Verify { email, verify_code } => {
+            variables.insert(
+                "VERIFY_LINK".to_string(),
+                serde_json::Value::String(
+                    format!("https://{}/verify?code={}", domain, verify_code)
+                )
+            );
+
+            (email, "Koii email verification", "koii-verify")
+        }
+        EmailRequest::Login { email, login_token, login_code } => {
+            variables.insert(
+                "LOGIN_LINK".to_string(),
+                serde_json::Value::String(
+                    format!("https://{}/login/email?token={}", domain, login_token)
+                )
+            );
+            variables.insert(
+                "LOGIN_CODE".to_string(),
+                serde_json::Value::String(login_code.clone())
+            );
+
+            (email, "Your Koii login link", "koii-login")
+        }
+        EmailRequest::Lockout { email } => {
+            (email, "Someone is trying to log in to your Koii account", "koii-lockout")
+        }
+        EmailRequest::Sudo { email, sudo_code } => {
+            variables.insert(
+                "SUDO_CODE".to_string(),
+                serde_json::Value::String(sudo_code.clone())
+            );
+
+            (email, "Your Koii confirmation code", "koii-sudo")
+        }
+    };
+
+    CreateEmailBaseOptions::new(
+        format!("Koii Auth <auth@{}>", domain),
+        [email],
+        subject
+    ).with_template(EmailTemplate::new(template).with_variables(variables))
+}
@@ -1,19 +1,15 @@
-use std::thread;
+use std::{ thread, time::Instant };
 
 use argon2::{ Argon2, password_hash::{ PasswordHasher, SaltString, rand_core::OsRng } };
-use tokio::sync::{ oneshot };
 
-use crate::env::{
-    ARGON2_MEMORY_COST,
-    ARGON2_OUTPUT_LENGTH,
-    ARGON2_PARALLELISM_COST,
-    ARGON2_TIME_COST,
+use crate::{
+    env::{ ARGON2_MEMORY_COST, ARGON2_OUTPUT_LENGTH, ARGON2_PARALLELISM_COST, ARGON2_TIME_COST },
+    utils::metrics,
+    workers::Job,
 };
 
 pub fn launch(
-    rx: kanal::AsyncReceiver<
-        (String, Option<oneshot::Sender<Result<String, argon2::password_hash::Error>>>)
-    >,
+    rx: kanal::AsyncReceiver<Job<String, Result<String, argon2::password_hash::Error>>>,
     threads: usize
 ) {
     let argon2id = Argon2::new(
@@ -37,12 +33,12 @@ pub fn launch(
 }
 
 fn worker(
-    rx: kanal::Receiver<
-        (String, Option<oneshot::Sender<Result<String, argon2::password_hash::Error>>>)
-    >,
+    rx: kanal::Receiver<Job<String, Result<String, argon2::password_hash::Error>>>,
     argon2id: Argon2
 ) {
-    while let Ok((password, Some(sender))) = rx.recv() {
+    while let Ok(Job { request: password, reply: Some(sender), span }) = rx.recv() {
+        let _span = tracing::info_span!(parent: &span, "hash_pass").entered();
+        let started = Instant::now();
         let salt = SaltString::generate(&mut OsRng);
         match argon2id.hash_password(password.as_bytes(), &salt) {
             Ok(hashed) => {
@@ -52,5 +48,6 @@ fn worker(
                 let _ = sender.send(Err(error));
             }
         }
+        metrics::job("hash_pass", started);
     }
 }
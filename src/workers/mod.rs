@@ -1,10 +1,22 @@
-use tokio::sync::oneshot;
+use std::{ sync::RwLock, time::Duration };
 
-use crate::workers::{ verify_email::VerifyEmailRequest, verify_pass::VerifyPassRequest };
+use thiserror::Error;
+use tokio::{ sync::oneshot, time::Instant };
+use tracing::Span;
+
+use crate::{
+    database::dead_letter::DeadLetterOperations,
+    workers::{
+        backchannel_logout::BackchannelLogoutRequest,
+        email::EmailRequest,
+        verify_pass::VerifyPassRequest,
+    },
+};
 
 pub mod hash_pass;
 pub mod verify_pass;
-pub mod verify_email;
+pub mod email;
+pub mod backchannel_logout;
 
 pub struct WorkerSpec {
     pub threads: usize,
@@ -15,16 +27,19 @@ pub struct WorkerSpec {
 pub struct WorkersAllocate {
     pub hash_pass: WorkerSpec,
     pub verify_pass: WorkerSpec,
-    pub verify_email: WorkerSpec,
+    pub email: WorkerSpec,
+    pub backchannel_logout: WorkerSpec,
 }
 
 pub struct Workers {
     pub hash_pass: RequestHandler<String, Result<String, argon2::password_hash::Error>>,
     pub verify_pass: RequestHandler<VerifyPassRequest, Result<bool, argon2::password_hash::Error>>,
-    pub verify_email: RequestHandler<VerifyEmailRequest, ()>,
+    pub email: RequestHandler<EmailRequest, ()>,
+    pub backchannel_logout: RequestHandler<BackchannelLogoutRequest, ()>,
 }
 impl Workers {
-    pub fn new(allocate: WorkersAllocate) -> Self {
+    /// Workers that can give up on a job record it in `dead_letter`.
+    pub fn new(allocate: WorkersAllocate, dead_letter: DeadLetterOperations) -> Self {
         tracing::info!("Spawning workers...");
 
         Workers {
@@ -38,46 +53,132 @@ impl Workers {
                 allocate.verify_pass.threads,
                 allocate.verify_pass.buffer
             ),
-            verify_email: RequestHandler::new(
-                verify_email::launch,
-                allocate.verify_email.threads,
-                allocate.verify_email.buffer
+            email: RequestHandler::new(
+                email::launch,
+                allocate.email.threads,
+                allocate.email.buffer
+            ),
+            backchannel_logout: RequestHandler::new(
+                |rx, threads| backchannel_logout::launch(rx, threads, dead_letter.clone()),
+                allocate.backchannel_logout.threads,
+                allocate.backchannel_logout.buffer
             ),
         }
     }
+
+    /// Lets every worker finish its queue, giving up on what's left after `timeout`.
+    ///
+    /// Only call this once the server stopped taking requests, sending afterwards fails.
+    pub async fn drain(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+
+        tokio::join!(
+            self.hash_pass.drain("hash_pass", deadline),
+            self.verify_pass.drain("verify_pass", deadline),
+            self.email.drain("email", deadline),
+            self.backchannel_logout.drain("backchannel_logout", deadline)
+        );
+    }
+}
+
+/// The workers were drained, nothing takes jobs anymore.
+#[derive(Error, Debug)]
+#[error("Workers were already shut down")]
+pub struct ShutDown;
+
+/// A request on its way to a worker, with the span it was sent from so the work shows up in
+/// the same trace.
+pub struct Job<R, P> {
+    pub request: R,
+    pub reply: Option<oneshot::Sender<P>>,
+    pub span: Span,
 }
 
 pub struct RequestHandler<R, P> {
-    tx: kanal::AsyncSender<(R, Option<oneshot::Sender<P>>)>,
+    /// Taken on shutdown, workers finish what is queued and exit once no sender is left.
+    tx: RwLock<Option<kanal::AsyncSender<Job<R, P>>>>,
+    /// Never received from, only there to tell when every worker has exited.
+    rx: kanal::AsyncReceiver<Job<R, P>>,
 }
 impl<R, P> RequestHandler<R, P> {
-    pub fn new<F: Fn(kanal::AsyncReceiver<(R, Option<oneshot::Sender<P>>)>, usize)>(
+    pub fn new<F: Fn(kanal::AsyncReceiver<Job<R, P>>, usize)>(
         launcher: F,
         threads: usize,
         buffer: usize
     ) -> Self {
-        let (service_tx, service_rx) = kanal::bounded_async::<(R, Option<oneshot::Sender<P>>)>(
-            buffer
-        );
-        launcher(service_rx, threads);
+        let (service_tx, service_rx) = kanal::bounded_async::<Job<R, P>>(buffer);
+        launcher(service_rx.clone(), threads);
 
         RequestHandler {
-            tx: service_tx,
+            tx: RwLock::new(Some(service_tx)),
+            rx: service_rx,
         }
     }
 
+    fn sender(&self) -> Result<kanal::AsyncSender<Job<R, P>>, ShutDown> {
+        self.tx.read().unwrap().clone().ok_or(ShutDown)
+    }
+
     /// Send a request to the worker and wait for data.
-    pub async fn send(&self, request: R) -> P {
+    pub async fn send(&self, request: R) -> Result<P, ShutDown> {
         let (one_tx, one_rx) = oneshot::channel::<P>();
 
         // Only fails when when all workers exited.
-        self.tx.send((request, Some(one_tx))).await.unwrap();
-        one_rx.await.unwrap()
+        self.sender()?.send(Job {
+            request,
+            reply: Some(one_tx),
+            span: Span::current(),
+        }).await.map_err(|_| ShutDown)?;
+        one_rx.await.map_err(|_| ShutDown)
     }
 
     /// Send a request to the worker, wait for it to finish, but ignore output from the worker.
+    ///
+    /// Dropped with a warning once the workers are drained.
     pub async fn send_ignore(&self, request: R) {
-        // Only fails when when all workers exited.
-        self.tx.send((request, None)).await.unwrap();
+        let sent = match self.sender() {
+            Ok(sender) => {
+                sender.send(Job {
+                    request,
+                    reply: None,
+                    span: Span::current(),
+                }).await.is_ok()
+            }
+            Err(_) => false,
+        };
+
+        if !sent {
+            tracing::warn!("Dropped a job sent after the workers were shut down.");
+        }
+    }
+
+    /// Requests waiting for a worker.
+    pub fn queued(&self) -> usize {
+        self.rx.len()
+    }
+
+    /// Requests that can wait before senders have to.
+    pub fn capacity(&self) -> usize {
+        self.rx.capacity()
+    }
+
+    async fn drain(&self, name: &str, deadline: Instant) {
+        self.tx.write().unwrap().take();
+
+        // Workers drop their receivers on exit, leaving only ours.
+        while self.rx.receiver_count() > 1 {
+            if Instant::now() >= deadline {
+                tracing::warn!(
+                    "Gave up on `{}` workers with {} requests still queued.",
+                    name,
+                    self.rx.len()
+                );
+                return;
+            }
+
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        tracing::info!("`{}` workers finished their queue.", name);
     }
 }
@@ -1,13 +1,11 @@
-use std::thread;
+use std::{ thread, time::Instant };
 
 use argon2::{ Argon2, PasswordHash, PasswordVerifier };
-use tokio::sync::oneshot;
 
-use crate::env::{
-    ARGON2_MEMORY_COST,
-    ARGON2_OUTPUT_LENGTH,
-    ARGON2_PARALLELISM_COST,
-    ARGON2_TIME_COST,
+use crate::{
+    env::{ ARGON2_MEMORY_COST, ARGON2_OUTPUT_LENGTH, ARGON2_PARALLELISM_COST, ARGON2_TIME_COST },
+    utils::metrics,
+    workers::Job,
 };
 
 pub struct VerifyPassRequest {
@@ -16,9 +14,7 @@ pub struct VerifyPassRequest {
 }
 
 pub fn launch(
-    rx: kanal::AsyncReceiver<
-        (VerifyPassRequest, Option<oneshot::Sender<Result<bool, argon2::password_hash::Error>>>)
-    >,
+    rx: kanal::AsyncReceiver<Job<VerifyPassRequest, Result<bool, argon2::password_hash::Error>>>,
     threads: usize
 ) {
     let argon2id = Argon2::new(
@@ -42,12 +38,12 @@ pub fn launch(
 }
 
 fn worker(
-    rx: kanal::Receiver<
-        (VerifyPassRequest, Option<oneshot::Sender<Result<bool, argon2::password_hash::Error>>>)
-    >,
+    rx: kanal::Receiver<Job<VerifyPassRequest, Result<bool, argon2::password_hash::Error>>>,
     argon2id: Argon2
 ) {
-    while let Ok((request, Some(sender))) = rx.recv() {
+    while let Ok(Job { request, reply: Some(sender), span }) = rx.recv() {
+        let _span = tracing::info_span!(parent: &span, "verify_pass").entered();
+        let started = Instant::now();
         match PasswordHash::new(&request.hash) {
             Ok(hash) => {
                 let _ = sender.send(
@@ -58,5 +54,6 @@ fn worker(
                 let _ = sender.send(Err(error));
             }
         }
+        metrics::job("verify_pass", started);
     }
 }
@@ -1,5 +1,5 @@
 use axum_test::{ TestResponse, TestServer };
-use koii::app;
+use koii::{ ops_router, router, state };
 use serde::{ Deserialize, Serialize };
 use serde_json::json;
 use reqwest::StatusCode;
@@ -7,12 +7,30 @@ use totp_rs::TOTP;
 
 #[tokio::test]
 async fn test_suite() {
+    // Counters in Redis would carry over from one run to the next.
+    unsafe { std::env::set_var("RATE_LIMIT_STORE", "memory") };
     koii::init();
-    let mut server = axum_test::TestServer::new(app(true).await);
+    let app_state = state(true).await;
+    let mut server = axum_test::TestServer::new(router(app_state.clone()));
+    let ops = axum_test::TestServer::new(ops_router(app_state));
 
     let correct_password = "a0*0h0*G)8g0dc08hcd";
     let wrong_password = "sd08h800)(H)9h0sdc";
 
+    // Liveness.
+    let response = server.get("/healthz").await;
+    response.assert_status(StatusCode::OK);
+    response.assert_json(&json!({"success": true, "result": "Alive."}));
+
+    // Metrics, the liveness request is already counted.
+    let response = ops.get("/metrics").await;
+    response.assert_status(StatusCode::OK);
+    assert!(response.text().contains("koii_http_requests_total"));
+
+    // Kept off the public router.
+    server.get("/metrics").await.assert_status(StatusCode::NOT_FOUND);
+    server.get("/readyz").await.assert_status(StatusCode::NOT_FOUND);
+
     // No account.
     let response = account_login(&server, correct_password, None).await;
     response.assert_status(StatusCode::NOT_FOUND);